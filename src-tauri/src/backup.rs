@@ -0,0 +1,482 @@
+use crate::content_index;
+use crate::filenames;
+use crate::folders::{self, FolderKind};
+use crate::log::{log_error, log_info, log_warn};
+use crate::settings;
+use chrono::{DateTime, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const BACKUP_ROOT: &str = "UploadIASD-backup";
+const MANIFESTS_DIR: &str = "manifests";
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static LAST_RUN: Mutex<Option<Value>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackupSettings {
+    // Pasta de destino (unidade externa, compartilhamento montado); vazio = desativado
+    pub destination: String,
+    // Intervalo entre cópias automáticas; 0 = apenas manualmente
+    pub interval_hours: u32,
+    // Conferir os hashes dos arquivos copiados ao final de cada execução
+    pub verify: bool,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        BackupSettings {
+            destination: String::new(),
+            interval_hours: 0,
+            verify: true,
+        }
+    }
+}
+
+impl BackupSettings {
+    pub fn validate(&mut self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        self.destination = self.destination.trim().to_string();
+        if !self.destination.is_empty() && !Path::new(&self.destination).is_absolute() {
+            problems.push(format!(
+                "Destino do backup precisa ser um caminho absoluto: {}",
+                self.destination
+            ));
+            self.destination.clear();
+        }
+        if self.interval_hours > 24 * 30 {
+            problems.push("Intervalo do backup deve ser de no máximo 720 horas".to_string());
+            self.interval_hours = 0;
+        }
+
+        problems
+    }
+}
+
+// Arquivo copiado, com caminho relativo à pasta de origem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub folder: String,
+    pub path: String,
+    pub size: u64,
+    pub modified: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub id: String,
+    pub started_at: String,
+    pub finished_at: String,
+    pub destination: String,
+    pub copied: usize,
+    pub unchanged: usize,
+    pub errors: Vec<String>,
+    pub verified: Option<bool>,
+    pub files: Vec<ManifestEntry>,
+}
+
+fn backup_root(destination: &Path) -> PathBuf {
+    destination.join(BACKUP_ROOT)
+}
+
+fn manifests_dir(destination: &Path) -> PathBuf {
+    backup_root(destination).join(MANIFESTS_DIR)
+}
+
+fn backup_file(destination: &Path, entry: &ManifestEntry) -> PathBuf {
+    backup_root(destination)
+        .join(&entry.folder)
+        .join(entry.path.replace('\\', "/"))
+}
+
+// Destino configurado e disponível (unidade externa pode estar desconectada)
+fn configured_destination() -> Result<PathBuf, String> {
+    let destination = settings::current().backup.destination;
+    if destination.is_empty() {
+        return Err("Nenhum destino de backup configurado".to_string());
+    }
+
+    let path = PathBuf::from(&destination);
+    folders::check_folder(&path, false)
+        .map_err(|e| format!("Destino do backup indisponível: {}", e))?;
+    Ok(path)
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Arquivos da pasta, inclusive subpastas, exceto ocultos e temporários
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log_warn(&format!("Não foi possível ler {}: {}", dir.display(), e));
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') || name.contains(".temp.") {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, out);
+        } else if path.is_file() {
+            out.push(path);
+        }
+    }
+}
+
+pub fn list() -> Result<Vec<Manifest>, String> {
+    let dir = manifests_dir(&configured_destination()?);
+    let mut manifests: Vec<Manifest> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .flatten()
+            .filter(|e| e.path().extension().and_then(|x| x.to_str()) == Some("json"))
+            .filter_map(|e| fs::read_to_string(e.path()).ok())
+            .filter_map(|content| serde_json::from_str(&content).ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    manifests.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+    Ok(manifests)
+}
+
+fn find(run_id: Option<&str>) -> Result<(PathBuf, Manifest), String> {
+    let destination = configured_destination()?;
+    let manifests = list()?;
+    let manifest = match run_id {
+        Some(id) => manifests.into_iter().find(|m| m.id == id),
+        None => manifests.into_iter().next(),
+    }
+    .ok_or("Backup não encontrado")?;
+    Ok((destination, manifest))
+}
+
+fn save_manifest(destination: &Path, manifest: &Manifest) -> Result<(), String> {
+    let dir = manifests_dir(destination);
+    fs::create_dir_all(&dir).map_err(|e| format!("Erro ao criar pasta de manifestos: {}", e))?;
+
+    let json_str = serde_json::to_string_pretty(manifest)
+        .map_err(|e| format!("Erro ao serializar manifesto: {}", e))?;
+    fs::write(dir.join(format!("{}.json", manifest.id)), json_str)
+        .map_err(|e| format!("Erro ao salvar manifesto: {}", e))
+}
+
+// Copiar para um temporário e renomear, preservando a data de modificação
+fn copy_file(from: &Path, to: &Path, modified: SystemTime) -> Result<(), String> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Erro ao criar {}: {}", parent.display(), e))?;
+    }
+
+    let temp = to.with_extension(format!(
+        "{}.backup-tmp",
+        to.extension().and_then(|e| e.to_str()).unwrap_or("")
+    ));
+    fs::copy(from, &temp).map_err(|e| format!("Erro ao copiar {}: {}", from.display(), e))?;
+    if let Ok(file) = fs::File::options().write(true).open(&temp) {
+        let _ = file.set_modified(modified);
+    }
+    fs::rename(&temp, to).map_err(|e| format!("Erro ao gravar {}: {}", to.display(), e))
+}
+
+// Cópia incremental: arquivos com tamanho e data iguais aos do último manifesto não são relidos
+fn run_backup() -> Result<Manifest, String> {
+    let destination = configured_destination()?;
+    let started_at = Local::now();
+
+    let previous: HashMap<(String, String), ManifestEntry> = list()?
+        .into_iter()
+        .next()
+        .map(|m| {
+            m.files
+                .into_iter()
+                .map(|f| ((f.folder.clone(), f.path.clone()), f))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut manifest = Manifest {
+        id: started_at.format("%Y%m%d-%H%M%S").to_string(),
+        started_at: started_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        finished_at: String::new(),
+        destination: destination.display().to_string(),
+        copied: 0,
+        unchanged: 0,
+        errors: Vec::new(),
+        verified: None,
+        files: Vec::new(),
+    };
+    let mut copied = Vec::new();
+
+    for kind in FolderKind::ALL {
+        let base_dir = match folders::resolve(kind) {
+            Ok(dir) => dir,
+            Err(e) => {
+                manifest.errors.push(e);
+                continue;
+            }
+        };
+        // Destino dentro da pasta de origem copiaria o próprio backup
+        if destination.starts_with(&base_dir) {
+            manifest.errors.push(format!(
+                "Destino do backup está dentro da pasta de {}",
+                kind.as_str()
+            ));
+            continue;
+        }
+
+        let mut files = Vec::new();
+        collect_files(&base_dir, &mut files);
+
+        for path in files {
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    manifest
+                        .errors
+                        .push(format!("Erro ao ler {}: {}", path.display(), e));
+                    continue;
+                }
+            };
+            let relative = path
+                .strip_prefix(&base_dir)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let mut entry = ManifestEntry {
+                folder: kind.as_str().to_string(),
+                path: relative,
+                size: metadata.len(),
+                modified: modified_secs(&metadata),
+                sha256: String::new(),
+            };
+            let target = backup_file(&destination, &entry);
+
+            let known = previous.get(&(entry.folder.clone(), entry.path.clone()));
+            if let Some(known) = known
+                .filter(|k| k.size == entry.size && k.modified == entry.modified && target.exists())
+            {
+                entry.sha256 = known.sha256.clone();
+                manifest.unchanged += 1;
+                manifest.files.push(entry);
+                continue;
+            }
+
+            entry.sha256 = match content_index::hash_file(&path) {
+                Ok(hash) => hash,
+                Err(e) => {
+                    manifest.errors.push(e);
+                    continue;
+                }
+            };
+
+            // Data mudou mas o conteúdo não: nada a copiar
+            if known.is_some_and(|k| k.sha256 == entry.sha256) && target.exists() {
+                manifest.unchanged += 1;
+                manifest.files.push(entry);
+                continue;
+            }
+
+            let modified = metadata.modified().unwrap_or_else(|_| SystemTime::now());
+            match copy_file(&path, &target, modified) {
+                Ok(()) => {
+                    manifest.copied += 1;
+                    copied.push(entry.clone());
+                    manifest.files.push(entry);
+                }
+                Err(e) => manifest.errors.push(e),
+            }
+        }
+    }
+
+    // Só o que foi copiado agora; a conferência completa fica com verify_backup
+    if settings::current().backup.verify {
+        let problems = verify_entries(&destination, &copied);
+        manifest.verified = Some(problems.is_empty());
+        manifest.errors.extend(problems);
+    }
+
+    manifest.finished_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    save_manifest(&destination, &manifest)?;
+
+    log_info(&format!(
+        "Backup {} concluído em {}: {} copiado(s), {} sem alteração, {} erro(s)",
+        manifest.id,
+        destination.display(),
+        manifest.copied,
+        manifest.unchanged,
+        manifest.errors.len()
+    ));
+    Ok(manifest)
+}
+
+// Conferir o hash de cada arquivo no destino
+fn verify_entries(destination: &Path, entries: &[ManifestEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let path = backup_file(destination, entry);
+            match content_index::hash_file(&path) {
+                Ok(hash) if hash == entry.sha256 => None,
+                Ok(_) => Some(format!("Cópia divergente: {}", path.display())),
+                Err(_) => Some(format!("Cópia ausente: {}", path.display())),
+            }
+        })
+        .collect()
+}
+
+pub fn verify(run_id: Option<&str>) -> Result<(Manifest, Vec<String>), String> {
+    let (destination, mut manifest) = find(run_id)?;
+    let problems = verify_entries(&destination, &manifest.files);
+
+    manifest.verified = Some(problems.is_empty());
+    save_manifest(&destination, &manifest)?;
+
+    if problems.is_empty() {
+        log_info(&format!(
+            "Backup {} verificado sem divergências",
+            manifest.id
+        ));
+    } else {
+        log_warn(&format!(
+            "Backup {} com {} divergência(s)",
+            manifest.id,
+            problems.len()
+        ));
+    }
+    Ok((manifest, problems))
+}
+
+// Restaurar um arquivo do backup para a pasta de origem, sem sobrescrever o existente
+pub fn restore(run_id: Option<&str>, folder: &str, path: &str) -> Result<PathBuf, String> {
+    let kind = FolderKind::parse(folder)?;
+    let (destination, manifest) = find(run_id)?;
+    let entry = manifest
+        .files
+        .iter()
+        .find(|f| f.folder == kind.as_str() && f.path == path)
+        .ok_or_else(|| format!("Arquivo não consta no backup {}: {}", manifest.id, path))?;
+
+    let relative = Path::new(&entry.path);
+    if relative
+        .components()
+        .any(|c| !matches!(c, std::path::Component::Normal(_)))
+    {
+        return Err(format!("Caminho inválido no manifesto: {}", entry.path));
+    }
+
+    let source = backup_file(&destination, entry);
+    if content_index::hash_file(&source)? != entry.sha256 {
+        return Err(format!(
+            "A cópia de {} foi substituída por uma versão mais recente e não corresponde a este backup",
+            path
+        ));
+    }
+
+    let target = filenames::next_free_path(&folders::resolve(kind)?.join(relative));
+    let modified = UNIX_EPOCH + Duration::from_secs(entry.modified);
+    copy_file(&source, &target, modified)?;
+
+    log_info(&format!(
+        "Arquivo restaurado do backup {}: {}",
+        manifest.id,
+        target.display()
+    ));
+    Ok(target)
+}
+
+// Backup em segundo plano; um por vez, com o resultado guardado para consulta
+pub fn start_run() -> bool {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return false;
+    }
+
+    std::thread::spawn(|| {
+        let result = match run_backup() {
+            Ok(manifest) => json!({
+                "id": manifest.id,
+                "finishedAt": manifest.finished_at,
+                "copied": manifest.copied,
+                "unchanged": manifest.unchanged,
+                "errors": manifest.errors,
+                "verified": manifest.verified
+            }),
+            Err(e) => {
+                log_error(&format!("Backup falhou: {}", e));
+                json!({ "error": e })
+            }
+        };
+
+        if let Ok(mut last) = LAST_RUN.lock() {
+            *last = Some(result);
+        }
+        RUNNING.store(false, Ordering::SeqCst);
+    });
+
+    true
+}
+
+pub fn status() -> Value {
+    json!({
+        "running": RUNNING.load(Ordering::SeqCst),
+        "lastRun": LAST_RUN.lock().ok().and_then(|last| last.clone())
+    })
+}
+
+// Término do backup concluído mais recente, pelos manifestos no destino
+fn last_finished() -> Option<DateTime<Local>> {
+    list()
+        .ok()?
+        .iter()
+        .filter_map(|m| NaiveDateTime::parse_from_str(&m.finished_at, "%Y-%m-%d %H:%M:%S").ok())
+        .max()?
+        .and_local_timezone(Local)
+        .earliest()
+}
+
+// Verificar periodicamente se um backup agendado está pendente
+pub fn start() {
+    std::thread::spawn(|| {
+        let mut last_run: Option<DateTime<Local>> = None;
+
+        loop {
+            std::thread::sleep(Duration::from_secs(60));
+            let config = settings::current().backup;
+            if config.interval_hours == 0 || config.destination.is_empty() {
+                continue;
+            }
+            // O app reinicia a cada culto; o intervalo conta a partir do último manifesto
+            if last_run.is_none() {
+                last_run = last_finished();
+            }
+
+            let due = last_run
+                .map(|last| last + chrono::Duration::hours(config.interval_hours as i64))
+                .is_none_or(|due| Local::now() >= due);
+            if due && start_run() {
+                last_run = Some(Local::now());
+            }
+        }
+    });
+}
@@ -0,0 +1,341 @@
+use crate::log::{log_info, log_warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+// Índice carregado uma vez e mantido em memória; o arquivo só é regravado quando muda
+static INDEX: Mutex<Option<ContentIndex>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub path: String,
+    pub size: u64,
+    // Data de modificação (segundos desde 1970) quando a entrada foi registrada
+    #[serde(default)]
+    pub modified: Option<u64>,
+    pub recorded_at: String,
+}
+
+// Índice de conteúdo: IDs de vídeos do YouTube e hashes SHA-256 dos arquivos
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ContentIndex {
+    #[serde(default)]
+    videos: HashMap<String, IndexEntry>,
+    #[serde(default)]
+    hashes: HashMap<String, IndexEntry>,
+}
+
+impl ContentIndex {
+    // Uma entrada por arquivo: o caminho agora guarda o conteúdo mais recente
+    fn forget_path(&mut self, path: &str) {
+        self.videos.retain(|_, entry| entry.path != path);
+        self.hashes.retain(|_, entry| entry.path != path);
+    }
+}
+
+fn index_file_path() -> PathBuf {
+    let app_data_dir = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
+    app_data_dir.join("UploadIASD").join("content-index.json")
+}
+
+fn load_index() -> ContentIndex {
+    let index_path = index_file_path();
+    let content = match fs::read_to_string(&index_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return ContentIndex::default(),
+        Err(e) => {
+            log_warn(&format!(
+                "Não foi possível ler o índice de conteúdo {}: {}",
+                index_path.display(),
+                e
+            ));
+            return ContentIndex::default();
+        }
+    };
+
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        log_warn(&format!(
+            "Índice de conteúdo ilegível, começando vazio: {}",
+            e
+        ));
+        ContentIndex::default()
+    })
+}
+
+fn save_index(index: &ContentIndex) -> Result<(), String> {
+    let index_path = index_file_path();
+    if let Some(parent) = index_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Erro ao criar diretório: {}", e))?;
+    }
+
+    let json_str = serde_json::to_string_pretty(index)
+        .map_err(|e| format!("Erro ao serializar índice de conteúdo: {}", e))?;
+
+    // Temporário + renomear, como no config.json: uma queda no meio não perde o índice
+    let temp_path = index_path.with_extension("json.tmp");
+    fs::write(&temp_path, json_str)
+        .map_err(|e| format!("Erro ao salvar índice de conteúdo: {}", e))?;
+    fs::rename(&temp_path, &index_path).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        format!("Erro ao salvar índice de conteúdo: {}", e)
+    })
+}
+
+// Executar com o índice em memória; `f` devolve se houve alteração a gravar
+fn with_index<T>(f: impl FnOnce(&mut ContentIndex) -> (T, bool)) -> Option<T> {
+    let mut guard = INDEX.lock().ok()?;
+    let index = guard.get_or_insert_with(load_index);
+    let (result, changed) = f(index);
+
+    if changed {
+        if let Err(e) = save_index(index) {
+            log_warn(&format!(
+                "Não foi possível atualizar índice de conteúdo: {}",
+                e
+            ));
+        }
+    }
+    Some(result)
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> Option<u64> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+}
+
+fn new_entry(path: &Path, size: u64) -> IndexEntry {
+    IndexEntry {
+        path: path.to_string_lossy().to_string(),
+        size,
+        modified: fs::metadata(path).ok().as_ref().and_then(modified_secs),
+        recorded_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    }
+}
+
+pub fn hash_bytes(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+pub fn hash_file(path: &Path) -> Result<String, String> {
+    let file =
+        fs::File::open(path).map_err(|e| format!("Erro ao abrir {}: {}", path.display(), e))?;
+    let mut reader = BufReader::new(file);
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|e| format!("Erro ao ler {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// Situação atual do arquivo de uma entrada do índice
+enum Check {
+    Unchanged,
+    // Tamanho ou data mudaram (ou entrada antiga, sem data): conteúdo precisa ser conferido
+    Changed(u64, Option<u64>),
+    Missing,
+}
+
+fn check(entry: &IndexEntry) -> Check {
+    match fs::metadata(&entry.path) {
+        Ok(metadata) if metadata.is_file() => {
+            let modified = modified_secs(&metadata);
+            if metadata.len() == entry.size
+                && entry.modified.is_some()
+                && modified == entry.modified
+            {
+                Check::Unchanged
+            } else {
+                Check::Changed(metadata.len(), modified)
+            }
+        }
+        _ => Check::Missing,
+    }
+}
+
+pub fn find_video(video_id: &str) -> Option<IndexEntry> {
+    with_index(|index| {
+        let entry = match index.videos.get(video_id) {
+            Some(entry) => entry.clone(),
+            None => return (None, false),
+        };
+
+        match check(&entry) {
+            Check::Unchanged => (Some(entry), false),
+            // Entrada antiga sem data, mesmo tamanho: completar com a data atual
+            Check::Changed(size, modified) if entry.modified.is_none() && size == entry.size => {
+                let entry = IndexEntry { modified, ..entry };
+                index.videos.insert(video_id.to_string(), entry.clone());
+                (Some(entry), true)
+            }
+            // Sumiu ou foi substituído (ex.: política overwrite): não dá para confirmar o vídeo
+            _ => {
+                index.videos.remove(video_id);
+                (None, true)
+            }
+        }
+    })
+    .flatten()
+}
+
+pub fn find_hash(hash: &str) -> Option<IndexEntry> {
+    with_index(|index| {
+        let entry = match index.hashes.get(hash) {
+            Some(entry) => entry.clone(),
+            None => return (None, false),
+        };
+
+        match check(&entry) {
+            Check::Unchanged => (Some(entry), false),
+            Check::Missing => {
+                index.hashes.remove(hash);
+                (None, true)
+            }
+            // Arquivo alterado: recalcular o hash antes de considerar duplicado
+            Check::Changed(size, modified) => {
+                let path = PathBuf::from(&entry.path);
+                index.hashes.remove(hash);
+                match hash_file(&path) {
+                    Ok(current) if current == hash => {
+                        let entry = IndexEntry {
+                            size,
+                            modified,
+                            ..entry
+                        };
+                        index.hashes.insert(current, entry.clone());
+                        (Some(entry), true)
+                    }
+                    Ok(current) => {
+                        index.hashes.insert(current, new_entry(&path, size));
+                        (None, true)
+                    }
+                    Err(e) => {
+                        log_warn(&e);
+                        (None, true)
+                    }
+                }
+            }
+        }
+    })
+    .flatten()
+}
+
+pub fn record_video(video_id: &str, path: &Path, size: u64) {
+    with_index(|index| {
+        let entry = new_entry(path, size);
+        index.forget_path(&entry.path);
+        index.videos.insert(video_id.to_string(), entry);
+        ((), true)
+    });
+}
+
+pub fn record_hash(hash: &str, path: &Path, size: u64) {
+    with_index(|index| {
+        let entry = new_entry(path, size);
+        index.forget_path(&entry.path);
+        index.hashes.insert(hash.to_string(), entry);
+        ((), true)
+    });
+}
+
+// Acompanhar arquivos movidos (reorganização de pastas)
+pub fn update_paths(moves: &[(PathBuf, PathBuf)]) {
+    with_index(|index| {
+        let mut changed = false;
+        for entry in index.videos.values_mut().chain(index.hashes.values_mut()) {
+            if let Some((_, to)) = moves
+                .iter()
+                .find(|(from, _)| from.to_string_lossy() == entry.path)
+            {
+                entry.path = to.to_string_lossy().to_string();
+                changed = true;
+            }
+        }
+        ((), changed)
+    });
+}
+
+// Reconstruir o índice a partir das pastas de uploads e vídeos; o hash é calculado sem
+// segurar o índice, para não travar a detecção de duplicados durante a reconstrução
+pub fn rebuild(folders: &[PathBuf]) -> Result<(usize, usize), String> {
+    let started_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut hashes: HashMap<String, IndexEntry> = HashMap::new();
+
+    let mut pending: Vec<PathBuf> = folders.iter().filter(|f| f.is_dir()).cloned().collect();
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log_warn(&format!("Não foi possível ler {}: {}", dir.display(), e));
+                continue;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            match hash_file(&path) {
+                Ok(hash) => {
+                    hashes.entry(hash).or_insert_with(|| new_entry(&path, size));
+                }
+                Err(e) => log_warn(&e),
+            }
+        }
+    }
+
+    let mut guard = INDEX
+        .lock()
+        .map_err(|e| format!("Erro ao acessar índice de conteúdo: {}", e))?;
+    let previous = guard.take().unwrap_or_else(load_index);
+
+    // Arquivos registrados enquanto a reconstrução rodava continuam valendo
+    for (hash, entry) in previous.hashes {
+        if entry.recorded_at >= started_at && Path::new(&entry.path).exists() {
+            hashes.entry(hash).or_insert(entry);
+        }
+    }
+
+    // IDs de vídeo não podem ser recalculados a partir do arquivo; manter os que ainda existem
+    let index = ContentIndex {
+        videos: previous
+            .videos
+            .into_iter()
+            .filter(|(_, entry)| Path::new(&entry.path).exists())
+            .collect(),
+        hashes,
+    };
+
+    let saved = save_index(&index);
+    let counts = (index.videos.len(), index.hashes.len());
+    *guard = Some(index);
+    drop(guard);
+    saved?;
+
+    log_info(&format!(
+        "Índice de conteúdo reconstruído: {} vídeo(s), {} arquivo(s)",
+        counts.0, counts.1
+    ));
+
+    Ok(counts)
+}
@@ -0,0 +1,222 @@
+use serde_json::json;
+
+// Limite do trecho do stderr enviado junto com o erro
+const MAX_DETAIL_CHARS: usize = 500;
+
+// Falhas conhecidas do yt-dlp, com código estável para a interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadError {
+    Network,
+    HttpForbidden,
+    RangeNotSatisfiable,
+    FormatUnavailable,
+    Unavailable,
+    GeoBlocked,
+    LiveNotStarted,
+    FfmpegMissing,
+    DiskFull,
+    OutdatedExtractor,
+    AgeRestricted,
+    MembersOnly,
+    SignInRequired,
+    Unknown,
+}
+
+impl DownloadError {
+    // Classificar a saída de erro do yt-dlp; a ordem importa, pois mensagens se sobrepõem
+    pub fn classify(stderr: &str) -> Self {
+        let text = stderr.to_lowercase();
+        let has = |patterns: &[&str]| patterns.iter().any(|p| text.contains(p));
+
+        if has(&[
+            "confirm your age",
+            "age-restricted",
+            "inappropriate for some users",
+        ]) {
+            DownloadError::AgeRestricted
+        } else if has(&[
+            "members-only",
+            "available to this channel's members",
+            "join this channel",
+        ]) {
+            DownloadError::MembersOnly
+        } else if has(&[
+            "sign in to confirm",
+            "--cookies for the authentication",
+            "login required",
+        ]) {
+            DownloadError::SignInRequired
+        } else if has(&[
+            "ffmpeg not found",
+            "ffprobe not found",
+            "ffmpeg is not installed",
+            "ffprobe and ffmpeg not found",
+        ]) {
+            DownloadError::FfmpegMissing
+        } else if has(&[
+            "no space left on device",
+            "errno 28",
+            "not enough space on the disk",
+        ]) {
+            DownloadError::DiskFull
+        } else if has(&[
+            "live event will begin",
+            "premieres in",
+            "is not currently live",
+            "waiting for scheduled stream",
+        ]) {
+            DownloadError::LiveNotStarted
+        } else if has(&[
+            "not available in your country",
+            "geo restriction",
+            "geo-restricted",
+            "blocked it in your country",
+        ]) {
+            DownloadError::GeoBlocked
+        } else if has(&["requested format is not available"]) {
+            DownloadError::FormatUnavailable
+        } else if has(&[
+            "private video",
+            "video unavailable",
+            "has been removed",
+            "no longer available",
+            "account associated with this video has been terminated",
+        ]) {
+            DownloadError::Unavailable
+        } else if has(&["http error 416", "requested range not satisfiable"]) {
+            DownloadError::RangeNotSatisfiable
+        } else if has(&["http error 403", "403: forbidden"]) {
+            DownloadError::HttpForbidden
+        } else if has(&[
+            "did not get any data blocks",
+            "unable to extract",
+            "nsig extraction failed",
+        ]) || extractor_bug(&text)
+        {
+            DownloadError::OutdatedExtractor
+        } else if has(&[
+            "unable to download webpage",
+            "urlopen error",
+            "timed out",
+            "connection reset",
+            "connection refused",
+            "remote end closed",
+            "network is unreachable",
+            "temporary failure in name resolution",
+            "getaddrinfo failed",
+        ]) {
+            DownloadError::Network
+        } else {
+            DownloadError::Unknown
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            DownloadError::Network => "network",
+            DownloadError::HttpForbidden => "http_403",
+            DownloadError::RangeNotSatisfiable => "http_416",
+            DownloadError::FormatUnavailable => "format_unavailable",
+            DownloadError::Unavailable => "video_unavailable",
+            DownloadError::GeoBlocked => "geo_blocked",
+            DownloadError::LiveNotStarted => "live_not_started",
+            DownloadError::FfmpegMissing => "ffmpeg_missing",
+            DownloadError::DiskFull => "disk_full",
+            DownloadError::OutdatedExtractor => "outdated_extractor",
+            DownloadError::AgeRestricted => "age_restricted",
+            DownloadError::MembersOnly => "members_only",
+            DownloadError::SignInRequired => "sign_in_required",
+            DownloadError::Unknown => "unknown",
+        }
+    }
+
+    pub fn message(self, cookies_configured: bool) -> &'static str {
+        match self {
+            DownloadError::Network => "Falha de conexão com o YouTube. Verifique sua internet e tente novamente.",
+            DownloadError::HttpForbidden => "O YouTube recusou o acesso ao vídeo (HTTP 403).",
+            DownloadError::RangeNotSatisfiable => "O download parcial ficou inconsistente (HTTP 416). Tente novamente.",
+            DownloadError::FormatUnavailable => "Nenhum formato disponível para a qualidade escolhida.",
+            DownloadError::Unavailable => "Vídeo privado, removido ou indisponível.",
+            DownloadError::GeoBlocked => "Vídeo bloqueado para a sua região.",
+            DownloadError::LiveNotStarted => "A transmissão ao vivo ainda não começou.",
+            DownloadError::FfmpegMissing => "FFmpeg não encontrado. Ele é necessário para converter e juntar áudio e vídeo.",
+            DownloadError::DiskFull => "Sem espaço em disco na pasta de vídeos.",
+            DownloadError::OutdatedExtractor => "O yt-dlp não conseguiu ler a página do YouTube; ele pode estar desatualizado.",
+            _ if cookies_configured && self.requires_login() => "O YouTube recusou os cookies configurados. Eles podem ter expirado: exporte novamente o cookies.txt e selecione-o em Configurações.",
+            DownloadError::AgeRestricted => "Vídeo com restrição de idade. Configure um arquivo de cookies de uma conta maior de idade em Configurações.",
+            DownloadError::MembersOnly => "Vídeo exclusivo para membros do canal. Configure um arquivo de cookies de uma conta membro em Configurações.",
+            DownloadError::SignInRequired => "O YouTube pediu login para este vídeo. Configure um arquivo de cookies em Configurações.",
+            DownloadError::Unknown => "Erro no download. Verifique a URL do vídeo e sua conexão com a internet.",
+        }
+    }
+
+    pub fn requires_login(self) -> bool {
+        matches!(
+            self,
+            DownloadError::AgeRestricted
+                | DownloadError::MembersOnly
+                | DownloadError::SignInRequired
+        )
+    }
+
+    // Vale tentar a próxima estratégia de formato
+    pub fn retry_with_other_format(self) -> bool {
+        matches!(
+            self,
+            DownloadError::Network
+                | DownloadError::HttpForbidden
+                | DownloadError::RangeNotSatisfiable
+                | DownloadError::FormatUnavailable
+                | DownloadError::Unknown
+        )
+    }
+
+    // Atualizar o yt-dlp costuma resolver (extrator quebrado, 403 por assinatura antiga
+    // ou erro não identificado)
+    pub fn needs_update(self) -> bool {
+        matches!(
+            self,
+            DownloadError::OutdatedExtractor
+                | DownloadError::HttpForbidden
+                | DownloadError::Unknown
+        )
+    }
+
+    // Erro serializado para a interface: {"code", "message", "detail", ...}
+    pub fn to_json(self, stderr: &str) -> String {
+        let cookies_configured = crate::get_cookies_file_path().exists();
+        json!({
+            "code": self.code(),
+            "message": self.message(cookies_configured),
+            "detail": error_detail(stderr),
+            "requiresLogin": self.requires_login(),
+            "cookiesConfigured": cookies_configured
+        })
+        .to_string()
+    }
+}
+
+// "please report this issue" acompanha qualquer erro inesperado do yt-dlp; só conta
+// quando vem de um extrator (linha "ERROR: [youtube] ...")
+fn extractor_bug(text: &str) -> bool {
+    text.lines().any(|line| {
+        line.trim_start().starts_with("error: [") && line.contains("please report this issue")
+    })
+}
+
+// Linha mais relevante do stderr (última com "ERROR", ou a última não vazia)
+pub fn error_detail(stderr: &str) -> String {
+    let lines: Vec<&str> = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    let line = lines
+        .iter()
+        .rfind(|l| l.contains("ERROR"))
+        .or(lines.last())
+        .copied()
+        .unwrap_or("");
+
+    line.chars().take(MAX_DETAIL_CHARS).collect()
+}
@@ -0,0 +1,296 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Limite do nome (sem extensão), deixando margem para ".temp.ext" e sufixos " (N)"
+pub const MAX_STEM_CHARS: usize = 150;
+
+pub const DEFAULT_DOWNLOAD_TEMPLATE: &str = "{title}.{ext}";
+pub const DEFAULT_UPLOAD_TEMPLATE: &str = "{name}.{ext}";
+
+pub const DOWNLOAD_PLACEHOLDERS: [&str; 8] = [
+    "date",
+    "time",
+    "upload_date",
+    "channel",
+    "title",
+    "id",
+    "quality",
+    "ext",
+];
+pub const UPLOAD_PLACEHOLDERS: [&str; 4] = ["date", "time", "name", "ext"];
+
+const WINDOWS_RESERVED: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+// O que fazer quando o arquivo de destino já existe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CollisionPolicy {
+    Overwrite,
+    #[default]
+    Suffix,
+    Skip,
+}
+
+// Resultado da aplicação da política de conflito
+pub enum Resolution {
+    // Gravar neste caminho (substituindo, se a política for overwrite)
+    Write(PathBuf),
+    // Arquivo já existe e a política é skip
+    Skip(PathBuf),
+}
+
+// Configuração de nomes aplicada a downloads e uploads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NamingSettings {
+    pub download_template: String,
+    pub upload_template: String,
+    pub collision_policy: CollisionPolicy,
+}
+
+impl Default for NamingSettings {
+    fn default() -> Self {
+        NamingSettings {
+            download_template: DEFAULT_DOWNLOAD_TEMPLATE.to_string(),
+            upload_template: DEFAULT_UPLOAD_TEMPLATE.to_string(),
+            collision_policy: CollisionPolicy::default(),
+        }
+    }
+}
+
+// Sanitizar um nome (sem extensão): caracteres inválidos, nomes reservados do Windows
+// e limite de tamanho
+pub fn sanitize_stem(stem: &str) -> String {
+    let mut cleaned: String = stem
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            _ if c.is_control() => '_',
+            _ => c,
+        })
+        .collect();
+
+    if cleaned.chars().count() > MAX_STEM_CHARS {
+        cleaned = cleaned.chars().take(MAX_STEM_CHARS).collect();
+    }
+
+    // Windows não aceita nomes terminados em ponto ou espaço
+    let cleaned = cleaned
+        .trim_end_matches(['.', ' '])
+        .trim_start()
+        .to_string();
+
+    if cleaned.is_empty() {
+        return "arquivo".to_string();
+    }
+
+    let base = cleaned
+        .split('.')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_uppercase();
+    if WINDOWS_RESERVED.contains(&base.as_str()) {
+        format!("_{}", cleaned)
+    } else {
+        cleaned
+    }
+}
+
+// Sanitizar nome completo de arquivo, preservando a extensão
+pub fn sanitize_file_name(file_name: &str) -> String {
+    let path = Path::new(file_name);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+
+    // ".bashrc" e similares: tratar o nome inteiro como stem
+    if stem.is_empty() || extension.is_empty() {
+        return sanitize_stem(file_name);
+    }
+
+    let extension: String = extension
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .take(16)
+        .collect();
+
+    if extension.is_empty() {
+        sanitize_stem(stem)
+    } else {
+        format!("{}.{}", sanitize_stem(stem), extension)
+    }
+}
+
+// Verificar se o modelo só usa marcadores conhecidos e produz algum nome
+pub fn validate_template(template: &str, placeholders: &[&str]) -> Result<(), String> {
+    let mut rest = template;
+    let mut found_name_part = false;
+
+    while let Some(open) = rest.find('{') {
+        let after = &rest[open + 1..];
+        let close = after
+            .find('}')
+            .ok_or_else(|| format!("Modelo com '{{' sem fechamento: {}", template))?;
+        let key = &after[..close];
+
+        if !placeholders.contains(&key) {
+            return Err(format!(
+                "Marcador desconhecido {{{}}}. Disponíveis: {}",
+                key,
+                placeholders
+                    .iter()
+                    .map(|p| format!("{{{}}}", p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if key != "ext" {
+            found_name_part = true;
+        }
+        rest = &after[close + 1..];
+    }
+
+    if !found_name_part {
+        return Err("O modelo precisa de ao menos um marcador além de {ext}".to_string());
+    }
+
+    Ok(())
+}
+
+const SEPARATORS: [char; 3] = ['_', '-', ' '];
+
+// Trecho do modelo já preenchido: texto literal ou valor de um marcador
+enum Part {
+    Text(String),
+    Value(String),
+}
+
+impl Part {
+    fn as_str(&self) -> &str {
+        match self {
+            Part::Text(text) | Part::Value(text) => text,
+        }
+    }
+
+    fn is_empty_value(&self) -> bool {
+        matches!(self, Part::Value(value) if value.is_empty())
+    }
+
+    // Só o texto do modelo é ajustado; valores ficam como vieram
+    fn trim_text(&mut self, start: bool) {
+        if let Part::Text(text) = self {
+            *text = if start {
+                text.trim_start_matches(SEPARATORS).to_string()
+            } else {
+                text.trim_end_matches(SEPARATORS).to_string()
+            };
+        }
+    }
+}
+
+// Gerar nome (sem extensão) a partir de um modelo como "{date}_{channel}_{title}.{ext}"
+pub fn render_stem(template: &str, values: &[(&str, String)], ext: &str) -> String {
+    let template = template.trim();
+    let template = template.strip_suffix(".{ext}").unwrap_or(template);
+
+    let mut parts: Vec<Part> = Vec::new();
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        parts.push(Part::Text(rest[..open].to_string()));
+        let after = &rest[open + 1..];
+        match after.find('}') {
+            Some(close) => {
+                let key = &after[..close];
+                let value = if key == "ext" {
+                    ext.to_string()
+                } else {
+                    values
+                        .iter()
+                        .find(|(k, _)| *k == key)
+                        .map(|(_, v)| v.trim().to_string())
+                        .unwrap_or_default()
+                };
+                parts.push(Part::Value(value));
+                rest = &after[close + 1..];
+            }
+            None => {
+                parts.push(Part::Text(rest[open..].to_string()));
+                rest = "";
+            }
+        }
+    }
+    parts.push(Part::Text(rest.to_string()));
+
+    // Marcador vazio deixaria separadores sobrando ("2026-10-19__titulo", "_titulo")
+    for index in 0..parts.len() {
+        if !parts[index].is_empty_value() {
+            continue;
+        }
+        let previous = (0..index).rev().find(|&i| !parts[i].as_str().is_empty());
+        let next = (index + 1..parts.len()).find(|&i| !parts[i].as_str().is_empty());
+        match (previous, next) {
+            (Some(p), Some(n)) => {
+                if parts[p].as_str().ends_with(SEPARATORS) {
+                    parts[n].trim_text(true);
+                }
+            }
+            (None, Some(n)) => parts[n].trim_text(true),
+            (Some(p), None) => parts[p].trim_text(false),
+            (None, None) => {}
+        }
+    }
+
+    let rendered: String = parts.iter().map(Part::as_str).collect();
+    sanitize_stem(&rendered)
+}
+
+// Próximo caminho livre no formato "nome (1).ext", "nome (2).ext"...
+pub fn next_free_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+
+    let parent = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("file")
+        .to_string();
+    let extension = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_string();
+
+    let mut counter = 1;
+    loop {
+        let new_filename = if extension.is_empty() {
+            format!("{} ({})", stem, counter)
+        } else {
+            format!("{} ({}).{}", stem, counter, extension)
+        };
+
+        let candidate = parent.join(&new_filename);
+        if !candidate.exists() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+// Aplicar a política de conflito ao caminho desejado
+pub fn resolve_collision(path: &Path, policy: CollisionPolicy) -> Resolution {
+    if !path.exists() {
+        return Resolution::Write(path.to_path_buf());
+    }
+
+    match policy {
+        CollisionPolicy::Overwrite => Resolution::Write(path.to_path_buf()),
+        CollisionPolicy::Suffix => Resolution::Write(next_free_path(path)),
+        CollisionPolicy::Skip => Resolution::Skip(path.to_path_buf()),
+    }
+}
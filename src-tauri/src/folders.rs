@@ -0,0 +1,196 @@
+use crate::log::{log_info, log_warn};
+use crate::settings::{self, Settings};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Pastas configuráveis do aplicativo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FolderKind {
+    Uploads,
+    Videos,
+}
+
+impl FolderKind {
+    pub const ALL: [FolderKind; 2] = [FolderKind::Uploads, FolderKind::Videos];
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "uploads" => Ok(FolderKind::Uploads),
+            "videos" => Ok(FolderKind::Videos),
+            other => Err(format!(
+                "Pasta desconhecida: {}. Use uploads ou videos.",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FolderKind::Uploads => "uploads",
+            FolderKind::Videos => "videos",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            FolderKind::Uploads => "uploads",
+            FolderKind::Videos => "vídeos",
+        }
+    }
+
+    // Chave correspondente no config.json
+    pub fn setting_key(self) -> &'static str {
+        match self {
+            FolderKind::Uploads => "uploadsPath",
+            FolderKind::Videos => "videosPath",
+        }
+    }
+
+    fn configured(self, settings: &Settings) -> String {
+        match self {
+            FolderKind::Uploads => settings.uploads_path.clone(),
+            FolderKind::Videos => settings.videos_path.clone(),
+        }
+    }
+
+    // Pasta padrão, sempre no disco do sistema
+    pub fn default_path(self) -> Result<PathBuf, String> {
+        match self {
+            FolderKind::Uploads => {
+                let app_data_dir = dirs::data_local_dir()
+                    .ok_or("Não foi possível encontrar diretório de dados")?;
+                Ok(app_data_dir.join("UploadIASD").join("uploads"))
+            }
+            FolderKind::Videos => {
+                let downloads_dir = dirs::download_dir()
+                    .ok_or("Não foi possível encontrar a pasta de Downloads")?;
+                Ok(downloads_dir.join("UploadIASD"))
+            }
+        }
+    }
+}
+
+// Situação de uma pasta configurada, exibida em Configurações
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderStatus {
+    pub kind: &'static str,
+    pub path: String,
+    pub using_default: bool,
+    pub available: bool,
+    pub free_bytes: Option<u64>,
+    pub error: Option<String>,
+    pub fallback_path: String,
+}
+
+// Gravar e apagar um arquivo de teste para confirmar permissão de escrita
+fn write_probe(path: &Path) -> Result<(), String> {
+    let probe = path.join(format!(".uploadiasd-teste-{}", uuid::Uuid::new_v4()));
+    fs::write(&probe, b"ok")
+        .map_err(|e| format!("Sem permissão de escrita em {}: {}", path.display(), e))?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+// Verificar pasta (absoluta, existente ou criável, gravável) e devolver o espaço livre
+pub fn check_folder(path: &Path, create: bool) -> Result<u64, String> {
+    if !path.is_absolute() {
+        return Err(format!("Caminho precisa ser absoluto: {}", path.display()));
+    }
+
+    if !path.exists() {
+        if !create {
+            return Err(format!("Pasta não encontrada: {}", path.display()));
+        }
+        fs::create_dir_all(path)
+            .map_err(|e| format!("Não foi possível criar {}: {}", path.display(), e))?;
+    }
+
+    if !path.is_dir() {
+        return Err(format!("O caminho não é uma pasta: {}", path.display()));
+    }
+
+    write_probe(path)?;
+
+    fs2::available_space(path).map_err(|e| {
+        format!(
+            "Erro ao consultar espaço livre em {}: {}",
+            path.display(),
+            e
+        )
+    })
+}
+
+pub fn format_free_space(bytes: u64) -> String {
+    format!("{:.1} GB livres", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+// Pasta efetiva; a configurada nunca é recriada em silêncio (pode ser um pendrive desconectado)
+pub fn resolve(kind: FolderKind) -> Result<PathBuf, String> {
+    let configured = kind.configured(&settings::current());
+
+    if configured.is_empty() {
+        let default_path = kind.default_path()?;
+        fs::create_dir_all(&default_path)
+            .map_err(|e| format!("Erro ao criar pasta padrão: {}", e))?;
+        return Ok(default_path);
+    }
+
+    let path = PathBuf::from(&configured);
+    if path.is_dir() {
+        return Ok(path);
+    }
+
+    Err(format!(
+        "Pasta de {} indisponível: {}. Reconecte a unidade ou use a pasta padrão em Configurações.",
+        kind.label(),
+        configured
+    ))
+}
+
+pub fn status(kind: FolderKind) -> FolderStatus {
+    let configured = kind.configured(&settings::current());
+    let fallback = kind.default_path().unwrap_or_default();
+    let using_default = configured.is_empty();
+    let path = if using_default {
+        fallback.clone()
+    } else {
+        PathBuf::from(&configured)
+    };
+
+    // A pasta padrão pode ser criada; a configurada precisa já existir
+    let check = check_folder(&path, using_default);
+
+    FolderStatus {
+        kind: kind.as_str(),
+        path: path.display().to_string(),
+        using_default,
+        available: check.is_ok(),
+        free_bytes: check.as_ref().ok().copied(),
+        error: check.err(),
+        fallback_path: fallback.display().to_string(),
+    }
+}
+
+// Na inicialização, avisar sobre pastas configuradas que ficaram indisponíveis
+pub fn report_startup() {
+    let unavailable: Vec<FolderStatus> = FolderKind::ALL
+        .iter()
+        .map(|kind| status(*kind))
+        .filter(|status| !status.available)
+        .collect();
+
+    for folder in &unavailable {
+        log_warn(&format!(
+            "Pasta de {} indisponível ({}); padrão disponível em {}",
+            folder.kind,
+            folder.error.as_deref().unwrap_or(""),
+            folder.fallback_path
+        ));
+    }
+
+    if unavailable.is_empty() {
+        log_info("Pastas configuradas verificadas");
+    }
+}
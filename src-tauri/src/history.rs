@@ -0,0 +1,797 @@
+use crate::folders::{self, FolderKind};
+use crate::log::{log_error, log_info, log_warn};
+use chrono::Local;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+// Versão do esquema, guardada em PRAGMA user_version
+const SCHEMA_VERSION: i32 = 2;
+
+// Conexão única compartilhada por comandos, servidor de upload e agendador
+static DB: Mutex<Option<Connection>> = Mutex::new(None);
+
+fn app_data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("UploadIASD")
+}
+
+fn database_path() -> PathBuf {
+    app_data_dir().join("history.db")
+}
+
+// Histórico antigo em JSON, importado uma única vez
+fn legacy_history_path() -> PathBuf {
+    app_data_dir().join("history.json")
+}
+
+// Origem da atividade, usada em filtros e estatísticas
+pub fn source_for(activity_type: &str) -> &'static str {
+    match activity_type {
+        "upload" => "upload",
+        "whatsapp_receive" => "whatsapp",
+        "youtube_download" | "livestream_capture" => "youtube",
+        _ => "other",
+    }
+}
+
+// Migrações de esquema, aplicadas em ordem a partir da versão atual do banco
+fn create_schema(conn: &Connection) -> rusqlite::Result<()> {
+    let version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    if version < 1 {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS activities (
+                id TEXT PRIMARY KEY,
+                type TEXT NOT NULL,
+                source TEXT NOT NULL,
+                file_path TEXT NOT NULL DEFAULT '',
+                file_name TEXT NOT NULL DEFAULT '',
+                file_size INTEGER NOT NULL DEFAULT 0,
+                metadata TEXT NOT NULL DEFAULT '',
+                timestamp INTEGER NOT NULL,
+                date TEXT NOT NULL,
+                details TEXT NOT NULL DEFAULT '{}'
+            );
+            CREATE INDEX IF NOT EXISTS idx_activities_type ON activities(type);
+            CREATE INDEX IF NOT EXISTS idx_activities_date ON activities(date);
+            CREATE INDEX IF NOT EXISTS idx_activities_source ON activities(source);
+            CREATE INDEX IF NOT EXISTS idx_activities_file_path ON activities(file_path);
+            -- Ações da retenção ficam fora de activities, para não contar como mídia em
+            -- estatísticas, relatórios e limpeza de arquivos ausentes
+            CREATE TABLE IF NOT EXISTS retention_audit (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                action TEXT NOT NULL,
+                rule TEXT NOT NULL DEFAULT '',
+                reason TEXT NOT NULL DEFAULT '',
+                path TEXT NOT NULL,
+                destination TEXT,
+                size INTEGER NOT NULL DEFAULT 0,
+                age_days INTEGER NOT NULL DEFAULT 0,
+                timestamp INTEGER NOT NULL,
+                date TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_retention_audit_timestamp ON retention_audit(timestamp);",
+        )?;
+    }
+
+    // Busca textual em nome do arquivo e metadados
+    if version < 2 {
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS activities_fts USING fts5(
+                file_name, metadata,
+                content='activities', content_rowid='rowid',
+                tokenize='unicode61 remove_diacritics 2'
+            );
+            CREATE TRIGGER IF NOT EXISTS activities_fts_insert AFTER INSERT ON activities BEGIN
+                INSERT INTO activities_fts(rowid, file_name, metadata)
+                VALUES (new.rowid, new.file_name, new.metadata);
+            END;
+            CREATE TRIGGER IF NOT EXISTS activities_fts_delete AFTER DELETE ON activities BEGIN
+                INSERT INTO activities_fts(activities_fts, rowid, file_name, metadata)
+                VALUES ('delete', old.rowid, old.file_name, old.metadata);
+            END;
+            CREATE TRIGGER IF NOT EXISTS activities_fts_update AFTER UPDATE ON activities BEGIN
+                INSERT INTO activities_fts(activities_fts, rowid, file_name, metadata)
+                VALUES ('delete', old.rowid, old.file_name, old.metadata);
+                INSERT INTO activities_fts(rowid, file_name, metadata)
+                VALUES (new.rowid, new.file_name, new.metadata);
+            END;
+            INSERT INTO activities_fts(activities_fts) VALUES ('rebuild');
+            CREATE INDEX IF NOT EXISTS idx_activities_file_size ON activities(file_size);
+            CREATE INDEX IF NOT EXISTS idx_activities_timestamp ON activities(timestamp);",
+        )?;
+    }
+
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)
+}
+
+// Campos conhecidos viram colunas; o restante vai para "details"
+fn insert(conn: &Connection, activity: &Value) -> rusqlite::Result<usize> {
+    let activity_type = activity["type"].as_str().unwrap_or("other");
+    let file_path = activity["file_path"].as_str().unwrap_or("");
+    let file_name = activity["file_name"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| file_name_of(file_path));
+    let timestamp = activity["timestamp"].as_i64().unwrap_or(0);
+
+    let mut details = activity.as_object().cloned().unwrap_or_default();
+    for key in [
+        "id",
+        "type",
+        "source",
+        "file_path",
+        "file_name",
+        "file_size",
+        "metadata",
+        "timestamp",
+        "date",
+    ] {
+        details.remove(key);
+    }
+
+    conn.execute(
+        "INSERT OR IGNORE INTO activities
+            (id, type, source, file_path, file_name, file_size, metadata, timestamp, date, details)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            activity["id"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| new_id(timestamp)),
+            activity_type,
+            source_for(activity_type),
+            file_path,
+            file_name,
+            activity["file_size"].as_i64().unwrap_or(0),
+            activity["metadata"].as_str().unwrap_or(""),
+            timestamp,
+            activity["date"].as_str().unwrap_or(""),
+            Value::Object(details).to_string(),
+        ],
+    )
+}
+
+// Importar history.json e renomeá-lo para não ser importado de novo; se estiver
+// ilegível, fica no lugar para uma nova tentativa
+fn migrate_legacy(conn: &mut Connection) -> Result<(), String> {
+    let legacy_path = legacy_history_path();
+    if !legacy_path.exists() {
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(&legacy_path)
+        .map_err(|e| format!("Erro ao ler histórico antigo: {}", e))?;
+    let entries: Vec<Value> = match serde_json::from_str(&content) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log_warn(&format!(
+                "Histórico antigo ilegível, migração adiada ({}): {}",
+                legacy_path.display(),
+                e
+            ));
+            return Ok(());
+        }
+    };
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Erro ao migrar histórico: {}", e))?;
+    let mut imported = 0;
+    for entry in &entries {
+        imported += insert(&tx, entry).map_err(|e| format!("Erro ao migrar histórico: {}", e))?;
+    }
+    tx.commit()
+        .map_err(|e| format!("Erro ao migrar histórico: {}", e))?;
+
+    fs::rename(&legacy_path, legacy_path.with_extension("json.migrated"))
+        .map_err(|e| format!("Erro ao arquivar histórico antigo: {}", e))?;
+
+    log_info(&format!(
+        "Histórico migrado para o banco de dados: {} atividade(s)",
+        imported
+    ));
+    Ok(())
+}
+
+fn open() -> Result<Connection, String> {
+    fs::create_dir_all(app_data_dir()).map_err(|e| format!("Erro ao criar diretório: {}", e))?;
+
+    let mut conn = Connection::open(database_path())
+        .map_err(|e| format!("Erro ao abrir banco de histórico: {}", e))?;
+    conn.busy_timeout(std::time::Duration::from_secs(5))
+        .map_err(|e| format!("Erro ao configurar banco de histórico: {}", e))?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| format!("Erro ao configurar banco de histórico: {}", e))?;
+    create_schema(&conn).map_err(|e| format!("Erro ao criar tabelas do histórico: {}", e))?;
+
+    if let Err(e) = migrate_legacy(&mut conn) {
+        log_error(&e);
+    }
+
+    Ok(conn)
+}
+
+// Executar uma operação com a conexão, abrindo o banco na primeira vez
+pub fn with_db<T>(f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let mut guard = DB
+        .lock()
+        .map_err(|e| format!("Erro ao acessar histórico: {}", e))?;
+    if guard.is_none() {
+        *guard = Some(open()?);
+    }
+
+    let conn = guard.as_mut().ok_or("Banco de histórico indisponível")?;
+    f(conn).map_err(|e| format!("Erro no banco de histórico: {}", e))
+}
+
+fn file_name_of(file_path: &str) -> String {
+    Path::new(file_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| "arquivo".to_string())
+}
+
+fn new_id(timestamp: i64) -> String {
+    format!(
+        "{}-{}",
+        timestamp,
+        uuid::Uuid::new_v4()
+            .to_string()
+            .chars()
+            .take(8)
+            .collect::<String>()
+    )
+}
+
+// Linha do banco no mesmo formato JSON usado pela interface
+pub fn row_to_json(row: &Row) -> rusqlite::Result<Value> {
+    let mut activity = json!({
+        "id": row.get::<_, String>("id")?,
+        "type": row.get::<_, String>("type")?,
+        "source": row.get::<_, String>("source")?,
+        "file_path": row.get::<_, String>("file_path")?,
+        "file_name": row.get::<_, String>("file_name")?,
+        "file_size": row.get::<_, i64>("file_size")?,
+        "metadata": row.get::<_, String>("metadata")?,
+        "timestamp": row.get::<_, i64>("timestamp")?,
+        "date": row.get::<_, String>("date")?,
+    });
+
+    let details: String = row.get("details")?;
+    if let (Some(entry), Ok(Value::Object(extra))) =
+        (activity.as_object_mut(), serde_json::from_str(&details))
+    {
+        for (key, value) in extra {
+            entry.entry(key).or_insert(value);
+        }
+    }
+
+    Ok(activity)
+}
+
+// Registrar atividade (campos adicionais em "details", ex.: "deduplicated": true)
+pub fn record(
+    activity_type: &str,
+    file_path: &str,
+    file_size: u64,
+    metadata: Option<&str>,
+    details: Value,
+) {
+    let timestamp = Local::now().timestamp();
+
+    let mut activity = json!({
+        "id": new_id(timestamp),
+        "type": activity_type, // "upload", "youtube_download", "whatsapp_receive", ...
+        "file_path": file_path,
+        "file_name": file_name_of(file_path),
+        "file_size": file_size,
+        "metadata": metadata.unwrap_or(""),
+        "timestamp": timestamp,
+        "date": Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+    });
+
+    if let (Some(entry), Value::Object(extra)) = (activity.as_object_mut(), details) {
+        entry.extend(extra);
+    }
+
+    if let Err(e) = with_db(|conn| insert(conn, &activity)) {
+        log_error(&format!("Erro ao registrar atividade: {}", e));
+    }
+}
+
+// Registrar uma ação da retenção (apagar ou arquivar) na trilha de auditoria
+pub fn record_retention(
+    action: &str,
+    rule: &str,
+    reason: &str,
+    path: &str,
+    destination: Option<&str>,
+    size: u64,
+    age_days: i64,
+) {
+    let now = Local::now();
+    let result = with_db(|conn| {
+        conn.execute(
+            "INSERT INTO retention_audit
+                (action, rule, reason, path, destination, size, age_days, timestamp, date)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                action,
+                rule,
+                reason,
+                path,
+                destination,
+                size as i64,
+                age_days,
+                now.timestamp(),
+                now.format("%Y-%m-%d %H:%M:%S").to_string()
+            ],
+        )
+    });
+
+    if let Err(e) = result {
+        log_error(&format!("Erro ao registrar ação da retenção: {}", e));
+    }
+}
+
+// Ações da retenção mais recentes primeiro
+pub fn retention_audit(limit: usize) -> Result<Vec<Value>, String> {
+    with_db(|conn| {
+        let mut stmt = conn
+            .prepare("SELECT * FROM retention_audit ORDER BY timestamp DESC, id DESC LIMIT ?1")?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(json!({
+                "action": row.get::<_, String>("action")?,
+                "rule": row.get::<_, String>("rule")?,
+                "reason": row.get::<_, String>("reason")?,
+                "path": row.get::<_, String>("path")?,
+                "destination": row.get::<_, Option<String>>("destination")?,
+                "size": row.get::<_, i64>("size")?,
+                "ageDays": row.get::<_, i64>("age_days")?,
+                "date": row.get::<_, String>("date")?,
+            }))
+        })?;
+        rows.collect()
+    })
+}
+
+// Marcar se o arquivo de cada atividade ainda existe (atividades sem arquivo contam como existentes)
+fn mark_existence(mut items: Vec<Value>) -> Vec<Value> {
+    for item in items.iter_mut() {
+        let exists = match item["file_path"].as_str() {
+            Some(path) if !path.is_empty() => Path::new(path).exists(),
+            _ => true,
+        };
+        item["file_exists"] = json!(exists);
+    }
+    items
+}
+
+// Atividades mais recentes primeiro, opcionalmente de um tipo
+pub fn recent(limit: usize, activity_type: Option<&str>) -> Result<Vec<Value>, String> {
+    with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT * FROM activities
+             WHERE ?1 IS NULL OR type = ?1
+             ORDER BY timestamp DESC, rowid DESC
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![activity_type, limit as i64], row_to_json)?;
+        rows.collect()
+    })
+    .map(mark_existence)
+}
+
+// Atividade mais recente registrada para um caminho
+pub fn find_by_path(file_path: &str) -> Result<Option<Value>, String> {
+    with_db(|conn| {
+        conn.query_row(
+            "SELECT * FROM activities WHERE file_path = ?1
+             ORDER BY timestamp DESC, rowid DESC LIMIT 1",
+            params![file_path],
+            row_to_json,
+        )
+        .optional()
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortField {
+    #[default]
+    Date,
+    Name,
+    Size,
+    Type,
+}
+
+impl SortField {
+    fn column(self) -> &'static str {
+        match self {
+            SortField::Date => "a.timestamp",
+            SortField::Name => "a.file_name COLLATE NOCASE",
+            SortField::Size => "a.file_size",
+            SortField::Type => "a.type",
+        }
+    }
+
+    fn key_column(self) -> &'static str {
+        match self {
+            SortField::Date => "timestamp",
+            SortField::Name => "file_name",
+            SortField::Size => "file_size",
+            SortField::Type => "type",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+// Filtros da tela de histórico; campos vazios não filtram
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryQuery {
+    pub search: Option<String>,
+    // AAAA-MM-DD, inclusivos
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub sources: Vec<String>,
+    pub types: Vec<String>,
+    pub sort: SortField,
+    pub order: SortOrder,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    // Cursor devolvido pela página anterior; tem prioridade sobre offset
+    pub cursor: Option<String>,
+}
+
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+pub const MAX_PAGE_SIZE: usize = 500;
+
+// Termos da busca como prefixos entre aspas, para o usuário não precisar da sintaxe FTS
+fn fts_expression(search: &str) -> Option<String> {
+    let terms: Vec<String> = search
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+fn parse_day(value: &str) -> Result<String, String> {
+    chrono::NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .map_err(|_| format!("Data inválida: {}. Use AAAA-MM-DD.", value))
+}
+
+// Cursor: JSON [valor da ordenação, rowid] do último item da página
+fn parse_cursor(cursor: &str) -> Result<(SqlValue, i64), String> {
+    let invalid = || "Cursor de paginação inválido".to_string();
+    let parsed: Value = serde_json::from_str(cursor).map_err(|_| invalid())?;
+
+    let key = match &parsed[0] {
+        Value::Number(n) => SqlValue::Integer(n.as_i64().ok_or_else(invalid)?),
+        Value::String(s) => SqlValue::Text(s.clone()),
+        _ => return Err(invalid()),
+    };
+    let row_id = parsed[1].as_i64().ok_or_else(invalid)?;
+
+    Ok((key, row_id))
+}
+
+// Condições SQL e valores correspondentes aos filtros da consulta
+fn filters(query: &HistoryQuery) -> Result<(Vec<String>, Vec<SqlValue>), String> {
+    let mut conditions: Vec<String> = Vec::new();
+    let mut values: Vec<SqlValue> = Vec::new();
+
+    if let Some(expression) = query.search.as_deref().and_then(fts_expression) {
+        conditions.push(
+            "a.rowid IN (SELECT rowid FROM activities_fts WHERE activities_fts MATCH ?)".into(),
+        );
+        values.push(SqlValue::Text(expression));
+    }
+    if let Some(from) = query.date_from.as_deref().filter(|d| !d.trim().is_empty()) {
+        conditions.push("a.date >= ?".into());
+        values.push(SqlValue::Text(parse_day(from)?));
+    }
+    if let Some(to) = query.date_to.as_deref().filter(|d| !d.trim().is_empty()) {
+        conditions.push("a.date <= ?".into());
+        values.push(SqlValue::Text(format!("{} 23:59:59", parse_day(to)?)));
+    }
+    if let (Some(min), Some(max)) = (query.min_size, query.max_size) {
+        if min > max {
+            return Err("Tamanho mínimo maior que o máximo".to_string());
+        }
+    }
+    if let Some(min) = query.min_size {
+        conditions.push("a.file_size >= ?".into());
+        values.push(SqlValue::Integer(min as i64));
+    }
+    if let Some(max) = query.max_size {
+        conditions.push("a.file_size <= ?".into());
+        values.push(SqlValue::Integer(max as i64));
+    }
+    for (column, list) in [("a.source", &query.sources), ("a.type", &query.types)] {
+        let list: Vec<&String> = list.iter().filter(|v| !v.trim().is_empty()).collect();
+        if list.is_empty() {
+            continue;
+        }
+        conditions.push(format!(
+            "{} IN ({})",
+            column,
+            vec!["?"; list.len()].join(", ")
+        ));
+        values.extend(list.iter().map(|v| SqlValue::Text(v.trim().to_string())));
+    }
+
+    Ok((conditions, values))
+}
+
+// Consulta paginada; devolve a página, o total filtrado e o cursor da próxima página
+pub fn query(query: &HistoryQuery) -> Result<Value, String> {
+    let (conditions, values) = filters(query)?;
+
+    let where_clause = |extra: Option<String>| {
+        let all: Vec<String> = conditions.iter().cloned().chain(extra).collect();
+        if all.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", all.join(" AND "))
+        }
+    };
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let column = query.sort.column();
+    let (direction, comparison) = match query.order {
+        SortOrder::Asc => ("ASC", ">"),
+        SortOrder::Desc => ("DESC", "<"),
+    };
+
+    let mut page_values = values.clone();
+    let (cursor_condition, offset) = match query.cursor.as_deref().filter(|c| !c.is_empty()) {
+        Some(cursor) => {
+            let (key, row_id) = parse_cursor(cursor)?;
+            page_values.push(key);
+            page_values.push(SqlValue::Integer(row_id));
+            (
+                Some(format!("({}, a.rowid) {} (?, ?)", column, comparison)),
+                0,
+            )
+        }
+        None => (None, query.offset.unwrap_or(0)),
+    };
+    page_values.push(SqlValue::Integer(limit as i64));
+    page_values.push(SqlValue::Integer(offset as i64));
+
+    let count_sql = format!("SELECT COUNT(*) FROM activities a {}", where_clause(None));
+    let page_sql = format!(
+        "SELECT a.rowid AS row_id, a.* FROM activities a {}
+         ORDER BY {} {dir}, a.rowid {dir} LIMIT ? OFFSET ?",
+        where_clause(cursor_condition),
+        column,
+        dir = direction
+    );
+    let key_column = query.sort.key_column();
+
+    let (total, items, last_key) = with_db(|conn| {
+        let total: i64 = conn.query_row(&count_sql, params_from_iter(values.iter()), |row| {
+            row.get(0)
+        })?;
+
+        let mut stmt = conn.prepare(&page_sql)?;
+        let mut items = Vec::new();
+        let mut last_key = None;
+        let mut rows = stmt.query(params_from_iter(page_values.iter()))?;
+        while let Some(row) = rows.next()? {
+            let key: SqlValue = row.get(key_column)?;
+            let row_id: i64 = row.get("row_id")?;
+            last_key = Some((key, row_id));
+            items.push(row_to_json(row)?);
+        }
+
+        Ok((total, items, last_key))
+    })?;
+    let items = mark_existence(items);
+
+    let next_cursor = match last_key {
+        Some((key, row_id)) if items.len() == limit => {
+            let key = match key {
+                SqlValue::Integer(n) => json!(n),
+                SqlValue::Text(s) => json!(s),
+                _ => Value::Null,
+            };
+            Some(json!([key, row_id]).to_string())
+        }
+        _ => None,
+    };
+
+    Ok(json!({
+        "total": total,
+        "offset": offset,
+        "limit": limit,
+        "items": items,
+        "nextCursor": next_cursor
+    }))
+}
+
+// Todas as atividades que atendem aos filtros, sem paginação (exportação e relatórios)
+pub fn matching(query: &HistoryQuery) -> Result<Vec<Value>, String> {
+    let (conditions, values) = filters(query)?;
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    let direction = match query.order {
+        SortOrder::Asc => "ASC",
+        SortOrder::Desc => "DESC",
+    };
+    let sql = format!(
+        "SELECT * FROM activities a {} ORDER BY {} {dir}, a.rowid {dir}",
+        where_clause,
+        query.sort.column(),
+        dir = direction
+    );
+
+    with_db(|conn| {
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values.iter()), row_to_json)?;
+        rows.collect()
+    })
+}
+
+// Atualizar caminhos de arquivos movidos
+pub fn update_paths(moves: &[(PathBuf, PathBuf)]) -> Result<usize, String> {
+    with_db(|conn| {
+        let tx = conn.transaction()?;
+        let mut updated = 0;
+        for (from, to) in moves {
+            let to_str = to.to_string_lossy();
+            updated += tx.execute(
+                "UPDATE activities SET file_path = ?2, file_name = ?3 WHERE file_path = ?1",
+                params![from.to_string_lossy(), to_str, file_name_of(&to_str)],
+            )?;
+        }
+        tx.commit()?;
+        Ok(updated)
+    })
+}
+
+pub fn get(id: &str) -> Result<Option<Value>, String> {
+    with_db(|conn| {
+        conn.query_row(
+            "SELECT * FROM activities WHERE id = ?1",
+            params![id],
+            row_to_json,
+        )
+        .optional()
+    })
+}
+
+pub fn delete(id: &str) -> Result<bool, String> {
+    with_db(|conn| conn.execute("DELETE FROM activities WHERE id = ?1", params![id])).map(|n| n > 0)
+}
+
+// Atividades cujo arquivo não existe mais (id, caminho)
+pub fn missing_files() -> Result<Vec<(String, String)>, String> {
+    let entries = with_db(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT id, file_path FROM activities WHERE file_path != ''")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<rusqlite::Result<Vec<(String, String)>>>()
+    })?;
+
+    Ok(entries
+        .into_iter()
+        .filter(|(_, path)| !Path::new(path).exists())
+        .collect())
+}
+
+// A pasta raiz do arquivo está acessível? Com a unidade desconectada não dá para
+// saber se o arquivo sumiu de fato
+fn root_available(path: &Path, roots: &[(PathBuf, bool)]) -> bool {
+    match roots.iter().find(|(root, _)| path.starts_with(root)) {
+        Some((_, available)) => *available,
+        // Fora das pastas configuradas (arquivamento, destino absoluto): exigir que a
+        // pasta do arquivo ainda exista
+        None => path.parent().is_some_and(Path::is_dir),
+    }
+}
+
+// Remover do histórico as atividades cujo arquivo sumiu; devolve removidas e ignoradas
+// (em pastas indisponíveis)
+pub fn prune_missing() -> Result<(usize, usize), String> {
+    let missing = missing_files()?;
+    if missing.is_empty() {
+        return Ok((0, 0));
+    }
+
+    let roots: Vec<(PathBuf, bool)> = FolderKind::ALL
+        .iter()
+        .map(|kind| {
+            let status = folders::status(*kind);
+            (PathBuf::from(status.path), status.available)
+        })
+        .collect();
+    let (prunable, skipped): (Vec<_>, Vec<_>) = missing
+        .into_iter()
+        .partition(|(_, path)| root_available(Path::new(path), &roots));
+
+    let removed = with_db(|conn| {
+        let tx = conn.transaction()?;
+        let mut removed = 0;
+        for (id, _) in &prunable {
+            removed += tx.execute("DELETE FROM activities WHERE id = ?1", params![id])?;
+        }
+        tx.commit()?;
+        Ok(removed)
+    })?;
+
+    Ok((removed, skipped.len()))
+}
+
+// Limpeza em segundo plano: uma por vez, com o resultado guardado para consulta
+static PRUNE_RUNNING: AtomicBool = AtomicBool::new(false);
+static LAST_PRUNE: Mutex<Option<Value>> = Mutex::new(None);
+
+pub fn start_prune() -> bool {
+    if PRUNE_RUNNING.swap(true, Ordering::SeqCst) {
+        return false;
+    }
+
+    std::thread::spawn(|| {
+        let outcome = prune_missing();
+        let finished_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let result = match outcome {
+            Ok((removed, skipped)) => {
+                log_info(&format!(
+                    "Histórico: {} atividade(s) sem arquivo removida(s), {} ignorada(s) por pasta indisponível",
+                    removed, skipped
+                ));
+                json!({ "removed": removed, "skipped": skipped, "finishedAt": finished_at })
+            }
+            Err(e) => {
+                log_error(&format!("Erro ao limpar histórico: {}", e));
+                json!({ "error": e, "finishedAt": finished_at })
+            }
+        };
+
+        if let Ok(mut last) = LAST_PRUNE.lock() {
+            *last = Some(result);
+        }
+        PRUNE_RUNNING.store(false, Ordering::SeqCst);
+    });
+
+    true
+}
+
+pub fn prune_status() -> Value {
+    json!({
+        "running": PRUNE_RUNNING.load(Ordering::SeqCst),
+        "lastResult": LAST_PRUNE.lock().ok().and_then(|last| last.clone())
+    })
+}
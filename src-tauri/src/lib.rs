@@ -1,4 +1,5 @@
 use semver::Version;
+use serde::Deserialize;
 use serde_json::{json, Value};
 use std::env;
 use std::fs;
@@ -7,11 +8,33 @@ use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
 use std::sync::Mutex;
 
+mod backup;
+mod content_index;
+mod download_error;
+mod filenames;
+mod folders;
+mod history;
 mod log;
+mod log_query;
+mod log_stream;
+mod network;
+mod organize;
+mod report;
+mod retention;
+mod routing;
+mod scheduler;
+mod settings;
+mod stats;
+mod subtitles;
 mod upload_server;
 
-use crate::log::{log_error, log_info, log_message, log_warn};
+use crate::download_error::DownloadError;
+use crate::log::{log_error, log_info, log_warn};
 use tauri_plugin_opener::OpenerExt;
+use tauri_plugin_store::StoreExt;
+
+// Mesmo arquivo aberto pela interface em settings-store.ts
+const SETTINGS_STORE_FILE: &str = "settings.json";
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -19,286 +42,181 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-// Comando para debug: verificar onde o store salva os arquivos
+// Comando para salvar caminho de uploads diretamente (alternativa ao Tauri Store)
 #[tauri::command]
-fn get_store_path() -> Result<String, String> {
-    let app_data_dir =
-        dirs::data_local_dir().ok_or("Não foi possível encontrar diretório de dados")?;
+fn set_uploads_path(path: String) -> Result<String, String> {
+    set_folder_path(folders::FolderKind::Uploads, &path)?;
+    Ok(format!("Caminho salvo com sucesso: {}", path))
+}
 
-    let identifier = "com.gabrielkramer.uploadiasddesktop";
-    let store_path = app_data_dir
-        .join(identifier)
-        .join("store")
-        .join("settings.json");
-
-    // Listar todos os caminhos possíveis
-    let possible_paths = vec![
-        store_path.clone(),
-        app_data_dir
-            .join("com.tauri.app")
-            .join("store")
-            .join("settings.json"),
-        app_data_dir.join("uploadiasddesktop").join("settings.json"),
-    ];
+// Comando para salvar caminho de vídeos diretamente (alternativa ao Tauri Store)
+#[tauri::command]
+fn set_videos_path(path: String) -> Result<String, String> {
+    set_folder_path(folders::FolderKind::Videos, &path)?;
+    Ok(format!("Caminho de vídeos salvo com sucesso: {}", path))
+}
 
-    let mut result = format!("Diretório de dados: {}\n\n", app_data_dir.display());
-    result.push_str("Caminhos verificados:\n");
-
-    for path in &possible_paths {
-        let exists = path.exists();
-        result.push_str(&format!(
-            "  {} - {}\n",
-            path.display(),
-            if exists {
-                "✅ EXISTE"
-            } else {
-                "❌ Não existe"
-            }
-        ));
+// Salvar a pasta configurada; settings::update já valida gravação e existência
+fn set_folder_path(kind: folders::FolderKind, path: &str) -> Result<(), String> {
+    let path = path.trim();
+    settings::update(&json!({ kind.setting_key(): path }))?;
 
-        if exists {
-            if let Ok(content) = fs::read_to_string(path) {
-                result.push_str(&format!(
-                    "    Conteúdo (primeiros 200 chars): {}\n",
-                    &content.chars().take(200).collect::<String>()
-                ));
-            }
-        }
-    }
-
-    // Verificar se há arquivos na pasta do identifier
-    let identifier_dir = app_data_dir.join(identifier);
-    if identifier_dir.exists() {
-        result.push_str(&format!("\n📁 Conteúdo de {}:\n", identifier_dir.display()));
-        if let Ok(entries) = fs::read_dir(&identifier_dir) {
-            for entry in entries.flatten() {
-                result.push_str(&format!("  - {}\n", entry.path().display()));
-            }
-        }
+    if !path.is_empty() {
+        let free = fs2::available_space(path)
+            .map(folders::format_free_space)
+            .unwrap_or_else(|_| "espaço livre desconhecido".to_string());
+        log_info(&format!(
+            "Pasta de {} salva: {} ({})",
+            kind.as_str(),
+            path,
+            free
+        ));
     }
-
-    Ok(result)
+    Ok(())
 }
 
-// Obter caminho do arquivo de configuração alternativo
-fn get_config_file_path() -> PathBuf {
-    let app_data_dir = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
-    app_data_dir.join("UploadIASD").join("config.json")
+// Situação das pastas configuradas: disponibilidade, espaço livre e pasta padrão
+#[tauri::command]
+fn get_folder_status() -> Result<String, String> {
+    let statuses: Vec<folders::FolderStatus> = folders::FolderKind::ALL
+        .iter()
+        .map(|kind| folders::status(*kind))
+        .collect();
+
+    serde_json::to_string(&statuses).map_err(|e| format!("Erro ao serializar pastas: {}", e))
 }
 
-// Comando para salvar caminho de uploads diretamente (alternativa ao Tauri Store)
+// Voltar para a pasta padrão quando a configurada ficou indisponível
 #[tauri::command]
-fn set_uploads_path(path: String) -> Result<String, String> {
-    let config_path = get_config_file_path();
+fn use_default_folder(kind: String) -> Result<String, String> {
+    let kind = folders::FolderKind::parse(&kind)?;
+    let default_path = kind.default_path()?;
+    folders::check_folder(&default_path, true)?;
+
+    settings::update(&json!({ kind.setting_key(): "" }))?;
+    log_warn(&format!(
+        "Pasta de {} redefinida para o padrão: {}",
+        kind.as_str(),
+        default_path.display()
+    ));
 
-    // Criar diretório se não existir
-    if let Some(parent) = config_path.parent() {
-        if let Err(e) = fs::create_dir_all(parent) {
-            return Err(format!("Erro ao criar diretório de configuração: {}", e));
-        }
-    }
+    Ok(default_path.display().to_string())
+}
 
-    // Ler configuração existente ou criar nova
-    let mut config: Value = if config_path.exists() {
-        if let Ok(content) = fs::read_to_string(&config_path) {
-            serde_json::from_str(&content).unwrap_or_else(|_| json!({}))
-        } else {
-            json!({})
-        }
-    } else {
-        json!({})
-    };
+#[tauri::command]
+fn get_settings(state: tauri::State<'_, settings::SharedSettings>) -> Result<String, String> {
+    let current = state
+        .read()
+        .map_err(|e| format!("Erro ao acessar configurações: {}", e))?;
+    serde_json::to_string(&*current).map_err(|e| format!("Erro ao serializar configuração: {}", e))
+}
 
-    // Atualizar caminho de uploads
-    config["uploadsPath"] = json!(path);
+// Recebe apenas os campos alterados, ex.: {"network": {"rateLimit": "2M"}}
+#[tauri::command]
+fn update_settings(changes: Value) -> Result<String, String> {
+    let updated = settings::update(&changes)?;
+    log_info("Configurações atualizadas");
+    serde_json::to_string(&updated).map_err(|e| format!("Erro ao serializar configuração: {}", e))
+}
 
-    // Salvar arquivo
-    let json_str = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Erro ao serializar configuração: {}", e))?;
+#[tauri::command]
+fn export_settings(path: String) -> Result<String, String> {
+    settings::export(&PathBuf::from(&path))?;
+    log_info(&format!("Configurações exportadas para {}", path));
+    Ok(format!("Configurações exportadas para {}", path))
+}
 
-    fs::write(&config_path, json_str).map_err(|e| format!("Erro ao salvar configuração: {}", e))?;
+#[tauri::command]
+fn import_settings(path: String) -> Result<String, String> {
+    let (imported, flagged) = settings::import(&PathBuf::from(&path))?;
+    log_info(&format!(
+        "Configurações importadas de {} ({} aviso(s))",
+        path,
+        flagged.len()
+    ));
+    for warning in &flagged {
+        log_warn(&format!("Importação de configurações: {}", warning));
+    }
 
-    Ok(format!("Caminho salvo com sucesso: {}", path))
+    Ok(json!({
+        "settings": imported,
+        "flagged": flagged
+    })
+    .to_string())
 }
 
-// Comando para salvar caminho de vídeos diretamente (alternativa ao Tauri Store)
+// Substitui a remoção manual do config.json e dos arquivos do plugin-store; o store
+// é limpo pela própria API, já que a interface mantém a mesma instância aberta
 #[tauri::command]
-fn set_videos_path(path: String) -> Result<String, String> {
-    let config_path = get_config_file_path();
+fn reset_settings(app: tauri::AppHandle) -> Result<String, String> {
+    let defaults = settings::reset()?;
 
-    // Criar diretório se não existir
-    if let Some(parent) = config_path.parent() {
-        if let Err(e) = fs::create_dir_all(parent) {
-            return Err(format!("Erro ao criar diretório de configuração: {}", e));
-        }
-    }
+    let store = app
+        .store(SETTINGS_STORE_FILE)
+        .map_err(|e| format!("Erro ao abrir store de configurações: {}", e))?;
+    store.clear();
+    store
+        .save()
+        .map_err(|e| format!("Erro ao salvar store de configurações: {}", e))?;
 
-    // Ler configuração existente ou criar nova
-    let mut config: Value = if config_path.exists() {
-        if let Ok(content) = fs::read_to_string(&config_path) {
-            serde_json::from_str(&content).unwrap_or_else(|_| json!({}))
-        } else {
-            json!({})
-        }
-    } else {
-        json!({})
-    };
-
-    // Atualizar caminho de vídeos
-    config["videosPath"] = json!(path);
+    log_info("Configurações restauradas para o padrão");
 
-    // Salvar arquivo
-    let json_str = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Erro ao serializar configuração: {}", e))?;
+    Ok(json!({ "settings": defaults }).to_string())
+}
 
-    fs::write(&config_path, json_str).map_err(|e| format!("Erro ao salvar configuração: {}", e))?;
+// Simular as regras de destino para um arquivo, sem gravar nada
+#[tauri::command]
+fn preview_route(
+    source: String,
+    file_name: String,
+    sender: Option<String>,
+    date: Option<String>,
+) -> Result<String, String> {
+    let source = routing::FileSource::parse(&source)?;
+    let base_dir = match source {
+        routing::FileSource::Youtube => get_videos_path()?,
+        _ => get_uploads_path()?,
+    };
+    let received_at = match date.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        Some(date) => chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("Data inválida: {}. Use AAAA-MM-DD.", date))?
+            .and_hms_opt(12, 0, 0)
+            .and_then(|dt| dt.and_local_timezone(chrono::Local).single())
+            .ok_or_else(|| format!("Data inválida: {}", date))?,
+        None => chrono::Local::now(),
+    };
 
-    Ok(format!("Caminho de vídeos salvo com sucesso: {}", path))
+    let rules = settings::current().routing_rules;
+    let incoming = routing::Incoming {
+        source,
+        file_name: &file_name,
+        sender: sender.as_deref(),
+        sender_id: None,
+        received_at,
+    };
+    let (destination, rule) = routing::evaluate(&rules, &incoming, &base_dir);
+
+    Ok(json!({
+        "destination": destination.display().to_string(),
+        "fileType": routing::classify_file_type(std::path::Path::new(&file_name)),
+        "rule": rule.map(|r| r.name.clone())
+    })
+    .to_string())
 }
 
 // Obter caminho de uploads das configurações ou usar padrão
 fn get_uploads_path() -> Result<PathBuf, String> {
-    let app_data_dir =
-        dirs::data_local_dir().ok_or("Não foi possível encontrar diretório de dados")?;
-
-    // PRIMEIRO: Tentar arquivo de configuração alternativo (mais confiável)
-    let config_path = get_config_file_path();
-    if config_path.exists() {
-        if let Ok(content) = fs::read_to_string(&config_path) {
-            if let Ok(json) = serde_json::from_str::<Value>(&content) {
-                if let Some(uploads_path) = json.get("uploadsPath").and_then(|v| v.as_str()) {
-                    if !uploads_path.is_empty() {
-                        let path = PathBuf::from(uploads_path);
-                        if !path.exists() {
-                            let _ = fs::create_dir_all(&path);
-                        }
-                        return Ok(path);
-                    }
-                }
-            }
-        }
-    }
-
-    // SEGUNDO: Tentar Tauri Store v2
-    let identifier = "com.gabrielkramer.uploadiasddesktop";
-    let store_path = app_data_dir
-        .join(identifier)
-        .join("store")
-        .join("settings.json");
-
-    let possible_paths = vec![
-        store_path.clone(),
-        app_data_dir
-            .join("com.tauri.app")
-            .join("store")
-            .join("settings.json"),
-        app_data_dir.join("uploadiasddesktop").join("settings.json"),
-    ];
-
-    for store_path in &possible_paths {
-        if store_path.exists() {
-            if let Ok(content) = fs::read_to_string(store_path) {
-                if let Ok(json) = serde_json::from_str::<Value>(&content) {
-                    let settings_obj = json.get("settings").or_else(|| Some(&json));
-
-                    if let Some(settings_obj) = settings_obj {
-                        if let Some(uploads_path) =
-                            settings_obj.get("uploadsPath").and_then(|v| v.as_str())
-                        {
-                            if !uploads_path.is_empty() {
-                                let path = PathBuf::from(uploads_path);
-                                if !path.exists() {
-                                    let _ = fs::create_dir_all(&path);
-                                }
-                                return Ok(path);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    // Fallback para padrão
-    let default_path = app_data_dir.join("UploadIASD").join("uploads");
-    fs::create_dir_all(&default_path).map_err(|e| format!("Erro ao criar pasta padrão: {}", e))?;
-    Ok(default_path)
+    folders::resolve(folders::FolderKind::Uploads)
 }
 
-// Obter caminho de vídeos das configurações ou usar padrão
+// Obter caminho de vídeos das configurações ou usar padrão (Downloads/UploadIASD)
 fn get_videos_path() -> Result<PathBuf, String> {
-    let app_data_dir =
-        dirs::data_local_dir().ok_or("Não foi possível encontrar diretório de dados")?;
-
-    // PRIMEIRO: Tentar arquivo de configuração alternativo (mais confiável)
-    let config_path = get_config_file_path();
-    if config_path.exists() {
-        if let Ok(content) = fs::read_to_string(&config_path) {
-            if let Ok(json) = serde_json::from_str::<Value>(&content) {
-                if let Some(videos_path) = json.get("videosPath").and_then(|v| v.as_str()) {
-                    if !videos_path.is_empty() {
-                        let path = PathBuf::from(videos_path);
-                        if !path.exists() {
-                            let _ = fs::create_dir_all(&path);
-                        }
-                        return Ok(path);
-                    }
-                }
-            }
-        }
-    }
-
-    // SEGUNDO: Tentar Tauri Store v2
-    let identifier = "com.gabrielkramer.uploadiasddesktop";
-    let store_path = app_data_dir
-        .join(identifier)
-        .join("store")
-        .join("settings.json");
-
-    let possible_paths = vec![
-        store_path.clone(),
-        app_data_dir
-            .join("com.tauri.app")
-            .join("store")
-            .join("settings.json"),
-        app_data_dir.join("uploadiasddesktop").join("settings.json"),
-    ];
-
-    for store_path in &possible_paths {
-        if store_path.exists() {
-            if let Ok(content) = fs::read_to_string(store_path) {
-                if let Ok(json) = serde_json::from_str::<Value>(&content) {
-                    let settings_obj = json.get("settings").or_else(|| Some(&json));
-
-                    if let Some(settings_obj) = settings_obj {
-                        if let Some(videos_path) =
-                            settings_obj.get("videosPath").and_then(|v| v.as_str())
-                        {
-                            if !videos_path.is_empty() {
-                                let path = PathBuf::from(videos_path);
-                                if !path.exists() {
-                                    let _ = fs::create_dir_all(&path);
-                                }
-                                return Ok(path);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    // Fallback para Downloads/UploadIASD
-    let downloads_dir =
-        dirs::download_dir().ok_or("Não foi possível encontrar a pasta de Downloads")?;
-    let default_path = downloads_dir.join("UploadIASD");
-    fs::create_dir_all(&default_path).map_err(|e| format!("Erro ao criar pasta padrão: {}", e))?;
-    Ok(default_path)
+    folders::resolve(folders::FolderKind::Videos)
 }
 
 // Sanitizar nome do arquivo
 fn sanitize_title(title: &str) -> String {
-    title
+    let sanitized = title
         .chars()
         .map(|c| match c {
             '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
@@ -308,7 +226,10 @@ fn sanitize_title(title: &str) -> String {
         .collect::<String>()
         .replace("__", "_")
         .trim_matches('_')
-        .to_string()
+        .to_string();
+
+    // Nomes reservados do Windows (CON, NUL...) e limite de tamanho
+    filenames::sanitize_stem(&sanitized)
 }
 
 // Atualizar yt-dlp
@@ -399,6 +320,103 @@ fn download_yt_dlp(path: &PathBuf) -> Result<(), String> {
     Ok(())
 }
 
+// Arquivo de cookies (formato Netscape) usado em todas as chamadas do yt-dlp
+fn get_cookies_file_path() -> PathBuf {
+    let app_data_dir = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
+    app_data_dir.join("UploadIASD").join("cookies.txt")
+}
+
+// Validar arquivo de cookies no formato Netscape; retorna a quantidade de cookies
+fn validate_netscape_cookies(content: &str) -> Result<usize, String> {
+    let mut cookies = 0;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        // Linhas "#HttpOnly_" são cookies válidos; demais "#" são comentários
+        if trimmed.is_empty() || (trimmed.starts_with('#') && !trimmed.starts_with("#HttpOnly_")) {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            return Err(
+                "Arquivo de cookies inválido. Exporte os cookies no formato Netscape (cookies.txt)."
+                    .to_string(),
+            );
+        }
+        cookies += 1;
+    }
+
+    if cookies == 0 {
+        return Err("Arquivo de cookies não contém nenhum cookie".to_string());
+    }
+
+    Ok(cookies)
+}
+
+// Criar comando do yt-dlp já com as opções globais (rede e cookies)
+fn yt_dlp_command(yt_dlp_path: &PathBuf) -> Command {
+    let mut cmd = Command::new(yt_dlp_path);
+    cmd.args(settings::current().network.yt_dlp_args());
+    let cookies_path = get_cookies_file_path();
+    if cookies_path.exists() {
+        cmd.arg("--cookies").arg(&cookies_path);
+    }
+    cmd
+}
+
+#[tauri::command]
+fn set_youtube_cookies(path: String) -> Result<String, String> {
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Erro ao ler arquivo de cookies: {}", e))?;
+    let count = validate_netscape_cookies(&content)?;
+
+    let cookies_path = get_cookies_file_path();
+    if let Some(parent) = cookies_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Erro ao criar diretório: {}", e))?;
+    }
+    fs::write(&cookies_path, content)
+        .map_err(|e| format!("Erro ao salvar arquivo de cookies: {}", e))?;
+
+    log_info(&format!(
+        "Cookies do YouTube atualizados ({} cookies)",
+        count
+    ));
+    Ok(format!("Cookies salvos com sucesso ({} cookies)", count))
+}
+
+#[tauri::command]
+fn clear_youtube_cookies() -> Result<String, String> {
+    let cookies_path = get_cookies_file_path();
+    if cookies_path.exists() {
+        fs::remove_file(&cookies_path)
+            .map_err(|e| format!("Erro ao remover arquivo de cookies: {}", e))?;
+        log_info("Cookies do YouTube removidos");
+    }
+    Ok("Cookies removidos".to_string())
+}
+
+#[tauri::command]
+fn get_youtube_cookies_status() -> Result<String, String> {
+    let cookies_path = get_cookies_file_path();
+    let updated_at = cookies_path
+        .metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .map(|t| {
+            chrono::DateTime::<chrono::Local>::from(t)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        });
+
+    Ok(json!({
+        "configured": cookies_path.exists(),
+        "path": cookies_path.display().to_string(),
+        "updatedAt": updated_at
+    })
+    .to_string())
+}
+
 // Limpar URL do YouTube, extraindo apenas o ID do vídeo e removendo parâmetros de playlist
 fn clean_youtube_url(url: &str) -> Result<String, String> {
     let url = url.trim();
@@ -457,6 +475,317 @@ fn get_quality_format(quality: &str) -> &str {
     }
 }
 
+// Formatos de saída suportados no download do YouTube
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DownloadFormat {
+    Mp3,
+    M4a,
+    Opus,
+    Wav,
+    Flac,
+    Mp4,
+    Mkv,
+    Webm,
+}
+
+impl DownloadFormat {
+    const ALL: [DownloadFormat; 8] = [
+        DownloadFormat::Mp3,
+        DownloadFormat::M4a,
+        DownloadFormat::Opus,
+        DownloadFormat::Wav,
+        DownloadFormat::Flac,
+        DownloadFormat::Mp4,
+        DownloadFormat::Mkv,
+        DownloadFormat::Webm,
+    ];
+
+    // "audio" e "video" continuam aceitos e equivalem a mp3 e mp4
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "audio" | "mp3" => Ok(DownloadFormat::Mp3),
+            "m4a" => Ok(DownloadFormat::M4a),
+            "opus" => Ok(DownloadFormat::Opus),
+            "wav" => Ok(DownloadFormat::Wav),
+            "flac" => Ok(DownloadFormat::Flac),
+            "video" | "mp4" => Ok(DownloadFormat::Mp4),
+            "mkv" => Ok(DownloadFormat::Mkv),
+            "webm" => Ok(DownloadFormat::Webm),
+            other => Err(format!(
+                "Formato não suportado: {}. Use mp3, m4a, opus, wav, flac, mp4, mkv ou webm.",
+                other
+            )),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            DownloadFormat::Mp3 => "mp3",
+            DownloadFormat::M4a => "m4a",
+            DownloadFormat::Opus => "opus",
+            DownloadFormat::Wav => "wav",
+            DownloadFormat::Flac => "flac",
+            DownloadFormat::Mp4 => "mp4",
+            DownloadFormat::Mkv => "mkv",
+            DownloadFormat::Webm => "webm",
+        }
+    }
+
+    fn is_audio(self) -> bool {
+        matches!(
+            self,
+            DownloadFormat::Mp3
+                | DownloadFormat::M4a
+                | DownloadFormat::Opus
+                | DownloadFormat::Wav
+                | DownloadFormat::Flac
+        )
+    }
+
+    // Apenas formatos com perda aceitam escolha de bitrate
+    fn is_lossy_audio(self) -> bool {
+        matches!(
+            self,
+            DownloadFormat::Mp3 | DownloadFormat::M4a | DownloadFormat::Opus
+        )
+    }
+
+    // Containers em que o yt-dlp consegue embutir a capa (WAV e WebM não suportam)
+    fn supports_thumbnail(self) -> bool {
+        !matches!(self, DownloadFormat::Wav | DownloadFormat::Webm)
+    }
+
+    // Preferência de container/codec para o seletor de formatos (-S) do yt-dlp
+    fn sort_preference(self) -> Option<&'static str> {
+        match self {
+            DownloadFormat::Mp4 => Some("ext:mp4:m4a"),
+            DownloadFormat::Webm => Some("ext:webm:webm"),
+            _ => None,
+        }
+    }
+}
+
+// Converter bitrate informado ("192", "192k", "192K") para o formato do yt-dlp
+fn parse_audio_bitrate(bitrate: Option<&str>) -> Result<Option<String>, String> {
+    let value = match bitrate.map(|b| b.trim()).filter(|b| !b.is_empty()) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    let kbps: u32 = value
+        .trim_end_matches(['k', 'K'])
+        .parse()
+        .map_err(|_| format!("Bitrate de áudio inválido: {}", value))?;
+
+    if !(32..=320).contains(&kbps) {
+        return Err(format!(
+            "Bitrate de áudio deve estar entre 32k e 320k (recebido: {})",
+            value
+        ));
+    }
+
+    Ok(Some(format!("{}K", kbps)))
+}
+
+// Argumentos de formato de cada tentativa de download, da preferida à mais permissiva
+fn build_download_attempts(
+    format: DownloadFormat,
+    quality: &str,
+    audio_bitrate: Option<&str>,
+) -> Vec<Vec<String>> {
+    let ext = format.extension().to_string();
+
+    if format.is_audio() {
+        let extract = |quality_arg: Option<&str>| {
+            let mut args = vec!["-x".to_string(), "--audio-format".to_string(), ext.clone()];
+            if let Some(q) = quality_arg {
+                args.push("--audio-quality".to_string());
+                args.push(q.to_string());
+            }
+            args
+        };
+
+        if format.is_lossy_audio() {
+            vec![
+                // Tentativa 1: Bitrate escolhido (ou melhor VBR)
+                extract(Some(audio_bitrate.unwrap_or("0"))),
+                // Tentativa 2: Qualidade menor
+                extract(Some("5")),
+                // Tentativa 3: Qualquer fonte de áudio
+                [vec!["-f".to_string(), "best".to_string()], extract(None)].concat(),
+            ]
+        } else {
+            vec![
+                // Tentativa 1: Melhor faixa de áudio disponível
+                [
+                    vec!["-f".to_string(), "bestaudio/best".to_string()],
+                    extract(None),
+                ]
+                .concat(),
+                // Tentativa 2: Seleção padrão do yt-dlp
+                extract(None),
+                // Tentativa 3: Qualquer fonte de áudio
+                [vec!["-f".to_string(), "best".to_string()], extract(None)].concat(),
+            ]
+        }
+    } else {
+        let container = |format_selector: &str| {
+            let mut args = vec!["-f".to_string(), format_selector.to_string()];
+            if let Some(sort) = format.sort_preference() {
+                args.push("-S".to_string());
+                args.push(sort.to_string());
+            }
+            args.extend([
+                "--merge-output-format".to_string(),
+                ext.clone(),
+                "--remux-video".to_string(),
+                ext.clone(),
+            ]);
+            args
+        };
+
+        vec![
+            // Tentativa 1: Qualidade solicitada
+            container(get_quality_format(quality)),
+            // Tentativa 2: Melhor qualidade disponível
+            container("best"),
+            // Tentativa 3: Qualquer formato de vídeo
+            container("bestvideo+bestaudio/best"),
+        ]
+    }
+}
+
+// Argumentos de marcação (ID3/MP4) e capa embutida no arquivo baixado
+fn build_tagging_args(
+    format: DownloadFormat,
+    embed_metadata: bool,
+    embed_thumbnail: bool,
+) -> Vec<String> {
+    let mut args = Vec::new();
+
+    if embed_metadata {
+        // Título e data vêm do --embed-metadata; artista e comentário são mapeados aqui
+        args.extend(
+            [
+                "--embed-metadata",
+                "--parse-metadata",
+                "%(channel,uploader|)s:%(meta_artist)s",
+                "--parse-metadata",
+                "%(webpage_url)s:%(meta_comment)s",
+            ]
+            .map(String::from),
+        );
+    }
+
+    if embed_thumbnail {
+        if format.supports_thumbnail() {
+            // JPEG é o formato de capa aceito pela maioria dos players e pelo ProPresenter
+            args.extend(["--embed-thumbnail", "--convert-thumbnails", "jpg"].map(String::from));
+        } else {
+            log_warn(&format!(
+                "Capa embutida não suportada em .{}; download seguirá sem capa",
+                format.extension()
+            ));
+        }
+    }
+
+    args
+}
+
+// Opções de legendas por download (idiomas, formato e transcrição)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubtitleOptions {
+    #[serde(default)]
+    languages: Vec<String>,
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    include_auto_captions: bool,
+    #[serde(default)]
+    transcript: bool,
+}
+
+impl SubtitleOptions {
+    fn requested_languages(&self) -> Vec<&str> {
+        self.languages
+            .iter()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .collect()
+    }
+
+    fn subtitle_format(&self) -> Result<String, String> {
+        match self.format.as_deref().map(|f| f.trim().to_lowercase()) {
+            None => Ok("srt".to_string()),
+            Some(f) if subtitles::SUBTITLE_EXTENSIONS.contains(&f.as_str()) => Ok(f),
+            Some(other) => Err(format!(
+                "Formato de legenda não suportado: {}. Use srt ou vtt.",
+                other
+            )),
+        }
+    }
+}
+
+// Argumentos para baixar legendas ao lado do arquivo final (Titulo.idioma.srt)
+fn build_subtitle_args(
+    options: &SubtitleOptions,
+    output_dir: &std::path::Path,
+    stem: &str,
+) -> Result<Vec<String>, String> {
+    let languages = options.requested_languages();
+    if languages.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let sub_format = options.subtitle_format()?;
+
+    let mut args = vec![
+        "--write-subs".to_string(),
+        "--sub-langs".to_string(),
+        languages.join(","),
+        "--sub-format".to_string(),
+        format!("{}/best", sub_format),
+        "--convert-subs".to_string(),
+        sub_format,
+        "-o".to_string(),
+        format!(
+            "subtitle:{}",
+            output_dir
+                .join(format!("{}.%(ext)s", stem))
+                .to_string_lossy()
+        ),
+    ];
+
+    if options.include_auto_captions {
+        args.push("--write-auto-subs".to_string());
+    }
+
+    Ok(args)
+}
+
+// Localizar o arquivo temporário gerado pelo yt-dlp, que pode não ter a extensão esperada
+// quando o remux/conversão não é possível
+fn find_temp_output(output_dir: &PathBuf, stem: &str, format: DownloadFormat) -> Option<PathBuf> {
+    let expected = output_dir.join(format!("{}.temp.{}", stem, format.extension()));
+    if expected.exists() {
+        return Some(expected);
+    }
+
+    let prefix = format!("{}.temp.", stem);
+    fs::read_dir(output_dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .find(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            path.is_file()
+                && name.starts_with(&prefix)
+                && DownloadFormat::ALL.iter().any(|f| f.extension() == ext)
+        })
+}
+
 #[tauri::command]
 fn get_video_info(url: String) -> Result<String, String> {
     // Limpar URL, extraindo apenas o ID do vídeo e removendo parâmetros de playlist
@@ -466,7 +795,7 @@ fn get_video_info(url: String) -> Result<String, String> {
     let yt_dlp_path = get_yt_dlp_path()?;
 
     // Obter metadados do vídeo
-    let mut info_cmd = Command::new(&yt_dlp_path);
+    let mut info_cmd = yt_dlp_command(&yt_dlp_path);
     info_cmd.args(&[
         "--dump-single-json",
         "--no-warnings",
@@ -480,20 +809,69 @@ fn get_video_info(url: String) -> Result<String, String> {
 
     if !info_output.status.success() {
         let stderr = String::from_utf8_lossy(&info_output.stderr);
-        return Err(format!("Erro ao obter informações: {}", stderr));
+        return Err(DownloadError::classify(&stderr).to_json(&stderr));
     }
 
-    // Retornar JSON como string
-    let json_str = String::from_utf8_lossy(&info_output.stdout);
-    Ok(json_str.to_string())
+    // Retornar JSON como string, com resumo dos idiomas de legenda disponíveis
+    let mut info_json: Value = serde_json::from_slice(&info_output.stdout)
+        .map_err(|_| "Erro ao processar informações do vídeo".to_string())?;
+    info_json["subtitle_languages"] = subtitles::summarize_languages(&info_json);
+    info_json["existing_file"] = info_json["id"]
+        .as_str()
+        .and_then(content_index::find_video)
+        .map(|entry| json!(entry.path))
+        .unwrap_or(Value::Null);
+
+    Ok(info_json.to_string())
+}
+
+// Exportar transcrição em texto puro a partir de um arquivo de legenda (.srt/.vtt)
+#[tauri::command]
+fn export_transcript(subtitle_path: String, output_path: Option<String>) -> Result<String, String> {
+    let output = subtitles::export_transcript(
+        &PathBuf::from(&subtitle_path),
+        output_path.as_deref().map(std::path::Path::new),
+    )?;
+
+    log_info(&format!(
+        "Transcrição exportada: {} -> {}",
+        subtitle_path,
+        output.display()
+    ));
+
+    Ok(output.display().to_string())
+}
+
+// Resultado do download: "downloaded", "duplicate" (já baixado antes) ou "skipped" (nome em uso)
+fn download_result(path: &std::path::Path, status: &str) -> String {
+    json!({
+        "path": path.display().to_string(),
+        "name": path.file_name().and_then(|n| n.to_str()).unwrap_or("arquivo"),
+        "status": status
+    })
+    .to_string()
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 fn download_youtube(
     url: String,
     format: String,
     quality: Option<String>,
+    audio_bitrate: Option<String>,
+    embed_metadata: Option<bool>,
+    embed_thumbnail: Option<bool>,
+    subtitles: Option<SubtitleOptions>,
+    allow_duplicate: Option<bool>,
 ) -> Result<String, String> {
+    // Validar formato e bitrate antes de qualquer chamada ao yt-dlp
+    let download_format = DownloadFormat::parse(&format)?;
+    let audio_quality = if download_format.is_lossy_audio() {
+        parse_audio_bitrate(audio_bitrate.as_deref())?
+    } else {
+        None
+    };
+
     // Limpar URL, extraindo apenas o ID do vídeo e removendo parâmetros de playlist
     let clean_url = clean_youtube_url(&url)?;
 
@@ -501,13 +879,13 @@ fn download_youtube(
     let yt_dlp_path = get_yt_dlp_path()?;
 
     // Obter pasta de vídeos (configurável)
-    let output_dir = get_videos_path()?;
+    let videos_dir = get_videos_path()?;
 
     // Criar pasta se não existir
-    fs::create_dir_all(&output_dir).map_err(|e| format!("Erro ao criar pasta: {}", e))?;
+    fs::create_dir_all(&videos_dir).map_err(|e| format!("Erro ao criar pasta: {}", e))?;
 
     // Primeiro, obter metadados do vídeo para gerar nome limpo (similar ao exemplo)
-    let mut info_cmd = Command::new(&yt_dlp_path);
+    let mut info_cmd = yt_dlp_command(&yt_dlp_path);
     info_cmd.args(&[
         "--dump-single-json",
         "--no-warnings",
@@ -521,7 +899,7 @@ fn download_youtube(
 
     if !info_output.status.success() {
         let stderr = String::from_utf8_lossy(&info_output.stderr);
-        return Err(format!("Erro ao obter informações: {}", stderr));
+        return Err(DownloadError::classify(&stderr).to_json(&stderr));
     }
 
     // Parse JSON para obter título
@@ -531,84 +909,160 @@ fn download_youtube(
     let title = info_json["title"].as_str().unwrap_or("video_sem_titulo");
 
     let sanitized_title = sanitize_title(title);
+    let video_id = info_json["id"].as_str().unwrap_or("").to_string();
+
+    // Vídeo já baixado antes: devolver o arquivo existente em vez de baixar de novo
+    if !video_id.is_empty()
+        && settings::current().duplicate_detection
+        && !allow_duplicate.unwrap_or(false)
+    {
+        if let Some(existing) = content_index::find_video(&video_id) {
+            let existing_path = PathBuf::from(&existing.path);
+            log_info(&format!(
+                "Vídeo {} já baixado em {}; download ignorado",
+                video_id, existing.path
+            ));
+
+            // Tamanho 0: os bytes já contam na atividade do download original
+            history::record(
+                "youtube_download",
+                &existing.path,
+                0,
+                Some(&sanitized_title),
+                json!({ "deduplicated": true, "duplicate_of": existing.path }),
+            );
+
+            return Ok(download_result(&existing_path, "duplicate"));
+        }
+    }
+
+    // Construir nome a partir do modelo configurado e aplicar a política de conflito
+    let naming = settings::current().naming;
+    let extension = download_format.extension();
+    let now = chrono::Local::now();
+    let upload_date = info_json["upload_date"]
+        .as_str()
+        .filter(|d| d.len() == 8)
+        .map(|d| format!("{}-{}-{}", &d[0..4], &d[4..6], &d[6..8]))
+        .unwrap_or_default();
+    let channel_name = info_json["channel"]
+        .as_str()
+        .or_else(|| info_json["uploader"].as_str());
+    let channel = channel_name.map(sanitize_title).unwrap_or_default();
+    let quality_label = if download_format.is_audio() {
+        audio_quality
+            .as_deref()
+            .map(|q| q.to_lowercase())
+            .unwrap_or_else(|| extension.to_string())
+    } else {
+        quality.clone().unwrap_or_else(|| "1080p".to_string())
+    };
+    let template_stem = filenames::render_stem(
+        &naming.download_template,
+        &[
+            ("date", now.format("%Y-%m-%d").to_string()),
+            ("time", now.format("%H-%M").to_string()),
+            ("upload_date", upload_date),
+            ("channel", channel),
+            ("title", sanitized_title.clone()),
+            ("id", video_id.clone()),
+            ("quality", quality_label),
+        ],
+        extension,
+    );
 
-    // Construir caminho do arquivo (similar ao exemplo)
-    let extension = if format == "audio" { "mp3" } else { "mp4" };
-    let output_file = output_dir.join(format!("{}.{}", sanitized_title, extension));
-    let temp_file = output_dir.join(format!("{}.temp.{}", sanitized_title, extension));
+    // Regras de destino (canal do YouTube faz o papel de remetente)
+    let file_name = format!("{}.{}", template_stem, extension);
+    let output_dir = routing::route(
+        &routing::Incoming {
+            source: routing::FileSource::Youtube,
+            file_name: &file_name,
+            sender: channel_name,
+            sender_id: info_json["channel_id"].as_str(),
+            received_at: now,
+        },
+        &videos_dir,
+    )?;
+    let output_dir =
+        organize::arrival_dir(&videos_dir, output_dir, &file_name, "youtube_download")?;
+
+    let desired_file = output_dir.join(&file_name);
+    let output_file = match filenames::resolve_collision(&desired_file, naming.collision_policy) {
+        filenames::Resolution::Write(path) => path,
+        filenames::Resolution::Skip(existing) => {
+            log_info(&format!(
+                "Download ignorado, arquivo já existe: {}",
+                existing.display()
+            ));
+            return Ok(download_result(&existing, "skipped"));
+        }
+    };
 
-    // Remover arquivo final anterior, se existir
+    // O yt-dlp escolhe a extensão do temporário (%(ext)s)
+    let file_stem = output_file
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(&template_stem)
+        .to_string();
+    let temp_template = output_dir.join(format!("{}.temp.%(ext)s", file_stem));
+
+    // Remover arquivo final anterior, se existir (política overwrite)
     if output_file.exists() {
         let _ = fs::remove_file(&output_file);
     }
 
     // Remover arquivo temporário anterior, se existir
-    if temp_file.exists() {
-        let _ = fs::remove_file(&temp_file);
+    while let Some(old_temp) = find_temp_output(&output_dir, &file_stem, download_format) {
+        if fs::remove_file(&old_temp).is_err() {
+            break;
+        }
     }
 
     // Construir comando de download com retry e fallback
-    let mut download_success = false;
-    let mut last_error = String::new();
-
-    // Tentar diferentes estratégias de download
-    let download_attempts = if format == "audio" {
-        vec![
-            // Tentativa 1: Formato preferido
-            vec!["-x", "--audio-format", "mp3", "--audio-quality", "0"],
-            // Tentativa 2: Qualidade menor
-            vec!["-x", "--audio-format", "mp3", "--audio-quality", "5"],
-            // Tentativa 3: Qualquer formato de áudio
-            vec!["-x", "--audio-format", "best"],
-        ]
-    } else {
-        let quality_format = get_quality_format(quality.as_deref().unwrap_or("1080p"));
-        vec![
-            // Tentativa 1: Qualidade solicitada
-            vec!["-f", quality_format],
-            // Tentativa 2: Melhor qualidade disponível
-            vec!["-f", "best"],
-            // Tentativa 3: Qualquer formato de vídeo
-            vec!["-f", "bestvideo+bestaudio/best"],
-        ]
+    // Margem para sistemas de arquivos com horário de modificação pouco preciso (FAT)
+    let download_started = std::time::SystemTime::now() - std::time::Duration::from_secs(2);
+    let mut download_success = false;
+    let mut last_error: Option<(DownloadError, String)> = None;
+
+    // Tentar diferentes estratégias de download
+    let download_attempts = build_download_attempts(
+        download_format,
+        quality.as_deref().unwrap_or("1080p"),
+        audio_quality.as_deref(),
+    );
+    let tagging_args = build_tagging_args(
+        download_format,
+        embed_metadata.unwrap_or(false),
+        embed_thumbnail.unwrap_or(false),
+    );
+    let subtitle_args = match &subtitles {
+        Some(options) => build_subtitle_args(options, &output_dir, &file_stem)?,
+        None => Vec::new(),
     };
 
     for (attempt_num, format_args) in download_attempts.iter().enumerate() {
-        let mut cmd = Command::new(&yt_dlp_path);
-
-        if format == "audio" {
-            cmd.args(format_args);
-            cmd.args(&[
-                "--no-warnings",
-                "--prefer-free-formats",
-                "--no-part",
-                "--no-mtime",
-                "--extractor-args",
-                "youtube:player_client=android",
-                "-o",
-                &temp_file.to_string_lossy(),
-                &clean_url,
-            ]);
-        } else {
-            cmd.args(format_args);
-            cmd.args(&[
-                "--no-warnings",
-                "--prefer-free-formats",
-                "--no-part",
-                "--no-mtime",
-                "--extractor-args",
-                "youtube:player_client=android",
-                "-o",
-                &temp_file.to_string_lossy(),
-                &clean_url,
-            ]);
-        }
+        let mut cmd = yt_dlp_command(&yt_dlp_path);
+        cmd.args(format_args);
+        cmd.args(&tagging_args);
+        cmd.args(&subtitle_args);
+        cmd.args(&[
+            "--no-warnings",
+            "--prefer-free-formats",
+            "--no-part",
+            "--no-mtime",
+            "-o",
+            &temp_template.to_string_lossy(),
+            &clean_url,
+        ]);
 
         // Executar comando de download
         let output = match cmd.output() {
             Ok(output) => output,
             Err(e) => {
-                last_error = format!("Erro ao executar yt-dlp: {}", e);
+                last_error = Some((
+                    DownloadError::Unknown,
+                    format!("Erro ao executar yt-dlp: {}", e),
+                ));
                 continue;
             }
         };
@@ -616,91 +1070,102 @@ fn download_youtube(
         if output.status.success() {
             download_success = true;
             break;
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            last_error = format!("Tentativa {} falhou: {}", attempt_num + 1, stderr);
+        }
 
-            // Se o erro for "Did not get any data blocks", tentar atualizar yt-dlp
-            if stderr.contains("Did not get any data blocks") || stderr.contains("ERROR") {
-                // Limpar arquivo temporário se existir
-                let _ = fs::remove_file(&temp_file);
-                continue;
-            }
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let error = DownloadError::classify(&stderr);
+        log_warn(&format!(
+            "Tentativa {} falhou [{}]: {}",
+            attempt_num + 1,
+            error.code(),
+            download_error::error_detail(&stderr)
+        ));
+
+        // Limpar arquivo temporário se existir
+        if let Some(temp_file) = find_temp_output(&output_dir, &file_stem, download_format) {
+            let _ = fs::remove_file(&temp_file);
+        }
+        last_error = Some((error, stderr));
+
+        // Login, vídeo removido, disco cheio, extrator quebrado...: outro formato não resolve
+        if !error.retry_with_other_format() {
+            break;
         }
     }
 
     if !download_success {
-        // Se o erro for "Did not get any data blocks", pode ser yt-dlp desatualizado
-        // Tentar atualizar e fazer uma última tentativa
-        if last_error.contains("Did not get any data blocks")
-            || last_error.contains("ERROR") && last_error.contains("yt-dlp")
-        {
-            // Tentar atualizar yt-dlp
-            if let Err(update_err) = update_yt_dlp(&yt_dlp_path) {
-                return Err(format!("Erro no download: {}. Falha ao atualizar yt-dlp: {}. Verifique sua conexão com a internet.", last_error, update_err));
-            }
+        let (error, stderr) = last_error.unwrap_or((DownloadError::Unknown, String::new()));
+        if !error.needs_update() {
+            return Err(error.to_json(&stderr));
+        }
 
-            // Última tentativa com yt-dlp atualizado
-            let mut cmd = Command::new(&yt_dlp_path);
-            if format == "audio" {
-                cmd.args(&[
-                    "-x",
-                    "--audio-format",
-                    "mp3",
-                    "--audio-quality",
-                    "0",
-                    "--no-warnings",
-                    "--prefer-free-formats",
-                    "--no-part",
-                    "--no-mtime",
-                    "--extractor-args",
-                    "youtube:player_client=android",
-                    "-o",
-                    &temp_file.to_string_lossy(),
-                    &clean_url,
-                ]);
-            } else {
-                cmd.args(&[
-                    "-f",
-                    "best",
-                    "--no-warnings",
-                    "--prefer-free-formats",
-                    "--no-part",
-                    "--no-mtime",
-                    "--extractor-args",
-                    "youtube:player_client=android",
-                    "-o",
-                    &temp_file.to_string_lossy(),
-                    &clean_url,
-                ]);
-            }
+        // Tentar atualizar o yt-dlp e fazer uma última tentativa
+        log_info(&format!("Atualizando yt-dlp após erro {}", error.code()));
+        if let Err(update_err) = update_yt_dlp(&yt_dlp_path) {
+            log_warn(&format!("Falha ao atualizar yt-dlp: {}", update_err));
+            return Err(error.to_json(&stderr));
+        }
 
-            let output = cmd
-                .output()
-                .map_err(|e| format!("Erro ao executar yt-dlp atualizado: {}", e))?;
+        // Última tentativa com yt-dlp atualizado, usando a estratégia mais permissiva
+        let mut cmd = yt_dlp_command(&yt_dlp_path);
+        if let Some(format_args) = download_attempts.last() {
+            cmd.args(format_args);
+        }
+        cmd.args(&tagging_args);
+        cmd.args(&subtitle_args);
+        cmd.args(&[
+            "--no-warnings",
+            "--prefer-free-formats",
+            "--no-part",
+            "--no-mtime",
+            "-o",
+            &temp_template.to_string_lossy(),
+            &clean_url,
+        ]);
+
+        let output = cmd.output().map_err(|e| {
+            DownloadError::Unknown.to_json(&format!("Erro ao executar yt-dlp atualizado: {}", e))
+        })?;
 
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return Err(format!("Erro no download após atualização: {}. Verifique a URL do vídeo e sua conexão com a internet.", stderr));
-            }
-            // Download bem-sucedido após atualização do yt-dlp
-        } else {
-            return Err(format!(
-                "Erro no download: {}. Verifique a URL do vídeo e sua conexão com a internet.",
-                last_error
-            ));
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(DownloadError::classify(&stderr).to_json(&stderr));
         }
+        // Download bem-sucedido após atualização do yt-dlp
     }
 
     // Aguardar o Windows liberar o arquivo (similar ao exemplo)
     std::thread::sleep(std::time::Duration::from_millis(1000));
 
     // Verificar se o download gerou o arquivo temporário (similar ao exemplo)
-    if !temp_file.exists() {
-        return Err(
-            "Download não gerou arquivo temporário. Verifique a URL e tente novamente.".to_string(),
-        );
-    }
+    let temp_file =
+        find_temp_output(&output_dir, &file_stem, download_format).ok_or_else(|| {
+            "Download não gerou arquivo temporário. Verifique a URL e tente novamente.".to_string()
+        })?;
+
+    // Se o yt-dlp não conseguiu converter para o formato escolhido, manter a extensão real;
+    // o novo nome passa de novo pela política de conflito
+    let output_file = match temp_file.extension().and_then(|e| e.to_str()) {
+        Some(actual_ext) if actual_ext != extension => {
+            log_warn(&format!(
+                "yt-dlp gerou .{} em vez de .{} para {}",
+                actual_ext, extension, file_stem
+            ));
+            let adjusted = output_dir.join(format!("{}.{}", file_stem, actual_ext));
+            match filenames::resolve_collision(&adjusted, naming.collision_policy) {
+                filenames::Resolution::Write(path) => path,
+                filenames::Resolution::Skip(existing) => {
+                    let _ = fs::remove_file(&temp_file);
+                    log_info(&format!(
+                        "Download descartado, arquivo já existe: {}",
+                        existing.display()
+                    ));
+                    return Ok(download_result(&existing, "skipped"));
+                }
+            }
+        }
+        _ => output_file,
+    };
 
     // Renomear arquivo temporário para final (similar ao exemplo)
     fs::rename(&temp_file, &output_file).map_err(|e| format!("Erro ao renomear arquivo: {}", e))?;
@@ -712,17 +1177,15 @@ fn download_youtube(
 
     let final_file_path = output_file;
 
-    // Remove quaisquer arquivos temporários restantes (similar ao exemplo)
+    // Remove os temporários e intermediários restantes deste download (ex.: .temp.webm
+    // antes da extração do áudio); os de outros downloads em andamento ficam
+    let own_temp_prefix = format!("{}.temp.", file_stem);
     if let Ok(entries) = fs::read_dir(&output_dir) {
         for entry in entries.flatten() {
             let path = entry.path();
             if path.is_file() {
                 if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                    if file_name.ends_with(".temp.mp4")
-                        || file_name.ends_with(".temp.temp.mp4")
-                        || file_name.ends_with(".temp.mp3")
-                        || file_name.ends_with(".temp.temp.mp3")
-                    {
+                    if file_name.starts_with(&own_temp_prefix) {
                         let _ = fs::remove_file(&path);
                     }
                 }
@@ -730,6 +1193,40 @@ fn download_youtube(
         }
     }
 
+    // Legendas baixadas ficam ao lado do arquivo final; gerar transcrição se solicitado
+    if let Some(options) = subtitles.as_ref().filter(|_| !subtitle_args.is_empty()) {
+        let subtitle_files = subtitles::find_subtitle_files(
+            &output_dir,
+            &file_stem,
+            &options.requested_languages(),
+            &options.subtitle_format()?,
+            download_started,
+        );
+        if subtitle_files.is_empty() {
+            log_warn(&format!(
+                "Nenhuma legenda encontrada nos idiomas solicitados para {}",
+                file_stem
+            ));
+        }
+
+        for subtitle_file in &subtitle_files {
+            log_info(&format!("Legenda baixada: {}", subtitle_file.display()));
+
+            if options.transcript {
+                match subtitles::export_transcript(subtitle_file, None) {
+                    Ok(transcript) => {
+                        log_info(&format!("Transcrição gerada: {}", transcript.display()))
+                    }
+                    Err(e) => log_warn(&format!(
+                        "Não foi possível gerar transcrição de {}: {}",
+                        subtitle_file.display(),
+                        e
+                    )),
+                }
+            }
+        }
+    }
+
     // Registrar atividade no histórico
     let file_size = final_file_path
         .metadata()
@@ -737,22 +1234,44 @@ fn download_youtube(
         .and_then(|m| Some(m.len()))
         .unwrap_or(0);
 
-    let _ = record_activity(
+    history::record(
         "youtube_download",
         &format!("{}", final_file_path.display()),
         file_size,
         Some(&sanitized_title),
+        Value::Null,
     );
 
-    // Retornar caminho completo do arquivo
-    Ok(format!(
-        "{}|{}",
-        final_file_path.display(),
-        final_file_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("arquivo")
-    ))
+    if !video_id.is_empty() {
+        content_index::record_video(&video_id, &final_file_path, file_size);
+    }
+
+    Ok(download_result(&final_file_path, "downloaded"))
+}
+
+// ==================== AGENDAMENTOS E GRAVAÇÃO DE TRANSMISSÕES ====================
+
+#[tauri::command]
+fn list_schedules() -> Result<String, String> {
+    Ok(json!(scheduler::list_rules()).to_string())
+}
+
+#[tauri::command]
+fn save_schedule(rule: scheduler::ScheduleRule) -> Result<String, String> {
+    let saved = scheduler::save_rule(rule)?;
+    Ok(json!(saved).to_string())
+}
+
+#[tauri::command]
+fn delete_schedule(id: String) -> Result<String, String> {
+    scheduler::delete_rule(&id)?;
+    Ok("Agendamento removido".to_string())
+}
+
+#[tauri::command]
+fn run_schedule_now(id: String) -> Result<String, String> {
+    scheduler::run_now(&id)?;
+    Ok("Gravação iniciada. Acompanhe o resultado no histórico.".to_string())
 }
 
 // Gerenciar processo do bot WhatsApp
@@ -837,6 +1356,122 @@ fn classify_whatsapp_output_level(stream_name: &str, line: &str) -> &'static str
     "INFO"
 }
 
+// Prefixo das linhas de evento (JSON) impressas pelo bot
+const WHATSAPP_EVENT_PREFIX: &str = "[UPLOADIASD_EVENT]";
+
+// Arquivo salvo pelo bot na pasta de uploads: aplicar as regras de destino
+fn handle_whatsapp_event(payload: &str) {
+    let event: Value = match serde_json::from_str(payload) {
+        Ok(event) => event,
+        Err(e) => {
+            log_warn(&format!(
+                "Evento inválido do bot WhatsApp: {} ({})",
+                payload, e
+            ));
+            return;
+        }
+    };
+
+    if event["event"].as_str() != Some("file_saved") {
+        return;
+    }
+
+    let saved_path = match event["path"].as_str() {
+        Some(path) => PathBuf::from(path),
+        None => return,
+    };
+    let (base_dir, file_name) = match (
+        saved_path.parent(),
+        saved_path.file_name().and_then(|n| n.to_str()),
+    ) {
+        (Some(parent), Some(name)) => (parent.to_path_buf(), name.to_string()),
+        _ => return,
+    };
+    let non_empty = |key: &str| event[key].as_str().filter(|v| !v.is_empty());
+
+    let destination = match routing::route(
+        &routing::Incoming {
+            source: routing::FileSource::Whatsapp,
+            file_name: &file_name,
+            sender: non_empty("sender"),
+            sender_id: non_empty("senderId"),
+            received_at: chrono::Local::now(),
+        },
+        &base_dir,
+    ) {
+        Ok(dir) => dir,
+        Err(e) => {
+            log_error(&e);
+            return;
+        }
+    };
+
+    // Sem regra: organização por data/tipo, se ativada
+    let received_path = saved_path.clone();
+    let final_path =
+        match organize::arrival_dir(&base_dir, destination, &file_name, "whatsapp_receive") {
+            Ok(dir) if dir != base_dir => {
+                let target = filenames::next_free_path(&dir.join(&file_name));
+                match fs::rename(&saved_path, &target) {
+                    Ok(()) => {
+                        log_info(&format!(
+                            "Arquivo do WhatsApp movido: {} -> {}",
+                            saved_path.display(),
+                            target.display()
+                        ));
+                        target
+                    }
+                    Err(e) => {
+                        log_error(&format!(
+                            "Erro ao mover arquivo do WhatsApp {}: {}",
+                            saved_path.display(),
+                            e
+                        ));
+                        saved_path
+                    }
+                }
+            }
+            Ok(_) => saved_path,
+            Err(e) => {
+                log_error(&e);
+                saved_path
+            }
+        };
+
+    // Manter histórico e índice de conteúdo apontando para o novo caminho, como na
+    // reorganização manual
+    if final_path != received_path {
+        let moved = [(received_path, final_path.clone())];
+        if let Err(e) = history::update_paths(&moved) {
+            log_warn(&format!("Não foi possível atualizar o histórico: {}", e));
+        }
+        content_index::update_paths(&moved);
+    }
+
+    // Remetente, conversa e legenda também entram nos metadados para a busca
+    let text = |key: &str| event[key].as_str().unwrap_or("").trim().to_string();
+    let metadata: Vec<String> = [text("sender"), text("chatName"), text("caption")]
+        .into_iter()
+        .filter(|v| !v.is_empty())
+        .collect();
+    let file_size = final_path.metadata().map(|m| m.len()).unwrap_or(0);
+
+    history::record(
+        "whatsapp_receive",
+        &final_path.to_string_lossy(),
+        file_size,
+        Some(&metadata.join(" · ")),
+        json!({
+            "sender": text("sender"),
+            "sender_id": text("senderId"),
+            "chat_name": text("chatName"),
+            "mime_type": text("mimeType"),
+            "caption": text("caption"),
+            "kind": text("kind")
+        }),
+    );
+}
+
 fn forward_whatsapp_output<R>(reader: R, stream_name: &'static str)
 where
     R: std::io::Read + Send + 'static,
@@ -851,6 +1486,11 @@ where
                         continue;
                     }
 
+                    if let Some(event) = trimmed.strip_prefix(WHATSAPP_EVENT_PREFIX) {
+                        handle_whatsapp_event(event.trim());
+                        continue;
+                    }
+
                     let payload = format!("[WA-BOT:{}] {}", stream_name, trimmed);
                     match classify_whatsapp_output_level(stream_name, trimmed) {
                         "ERROR" => log_error(&payload),
@@ -1306,276 +1946,380 @@ fn get_whatsapp_status() -> Result<String, String> {
 }
 // ==================== SISTEMA DE HISTÓRICO E ATIVIDADES ====================
 
-// Obter caminho do arquivo de histórico
-fn get_history_file_path() -> PathBuf {
-    let app_data_dir = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
-    app_data_dir.join("UploadIASD").join("history.json")
+// Reorganizar por data/tipo os arquivos soltos nas pastas; por padrão só simula
+#[tauri::command]
+fn reorganize_folders(dry_run: Option<bool>) -> Result<String, String> {
+    let folders = vec![
+        (get_uploads_path()?, "upload"),
+        (get_videos_path()?, "youtube_download"),
+    ];
+    let moves = organize::plan(&folders);
+
+    if dry_run.unwrap_or(true) {
+        return Ok(json!({ "dryRun": true, "moves": moves }).to_string());
+    }
+
+    let (batch, errors) = organize::apply(&moves)?;
+    Ok(json!({
+        "dryRun": false,
+        "batchId": batch.as_ref().map(|b| b.id.clone()),
+        "moved": batch.map(|b| b.moves.len()).unwrap_or(0),
+        "errors": errors
+    })
+    .to_string())
 }
 
-// Registrar atividade no histórico
-fn record_activity(
-    activity_type: &str,
-    file_path: &str,
-    file_size: u64,
-    metadata: Option<&str>,
-) -> Result<(), String> {
-    let history_path = get_history_file_path();
+// Desfazer uma reorganização (a mais recente, se nenhuma for indicada)
+#[tauri::command]
+fn undo_reorganization(batch_id: Option<String>) -> Result<String, String> {
+    let (batch, restored, errors) = organize::undo(batch_id.as_deref())?;
+
+    Ok(json!({
+        "batchId": batch.id,
+        "restored": restored,
+        "errors": errors
+    })
+    .to_string())
+}
 
-    // Criar diretório se não existir
-    if let Some(parent) = history_path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("Erro ao criar diretório: {}", e))?;
-    }
+// Reorganizações aplicadas que ainda podem ser desfeitas
+#[tauri::command]
+fn list_reorganizations() -> Result<String, String> {
+    let batches: Vec<Value> = organize::load_journal()
+        .iter()
+        .rev()
+        .map(|b| json!({ "id": b.id, "createdAt": b.created_at, "files": b.moves.len() }))
+        .collect();
+
+    Ok(json!(batches).to_string())
+}
 
-    // Ler histórico existente
-    let mut history: Vec<Value> = if history_path.exists() {
-        if let Ok(content) = fs::read_to_string(&history_path) {
-            serde_json::from_str(&content).unwrap_or_else(|_| vec![])
-        } else {
-            vec![]
-        }
-    } else {
-        vec![]
-    };
+// Obter histórico de atividades
+#[tauri::command]
+fn get_activity_history(
+    limit: Option<usize>,
+    activity_type: Option<String>,
+) -> Result<String, String> {
+    let history = history::recent(limit.unwrap_or(100), activity_type.as_deref())?;
 
-    // Criar entrada de atividade
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-
-    let activity = json!({
-        "id": format!("{}-{}", timestamp, uuid::Uuid::new_v4().to_string().chars().take(8).collect::<String>()),
-        "type": activity_type, // "upload", "youtube_download", "whatsapp_receive"
-        "file_path": file_path,
-        "file_name": PathBuf::from(file_path).file_name()
-            .and_then(|n| n.to_str())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| "arquivo".to_string()),
-        "file_size": file_size,
-        "metadata": metadata.unwrap_or(""),
-        "timestamp": timestamp,
-        "date": chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
-    });
+    Ok(json!(history).to_string())
+}
+
+// Consultar histórico com busca, filtros, ordenação e paginação
+#[tauri::command]
+fn query_activity_history(query: Option<history::HistoryQuery>) -> Result<String, String> {
+    let page = history::query(&query.unwrap_or_default())?;
+
+    Ok(page.to_string())
+}
 
-    // Adicionar no início (mais recente primeiro)
-    history.insert(0, activity);
+// Atividade do histórico cujo arquivo ainda existe
+fn history_file(id: &str) -> Result<(Value, PathBuf), String> {
+    let entry = history::get(id)?.ok_or_else(|| format!("Atividade não encontrada: {}", id))?;
+    let path = PathBuf::from(entry["file_path"].as_str().unwrap_or(""));
 
-    // Manter apenas últimos 1000 registros
-    if history.len() > 1000 {
-        history.truncate(1000);
+    if path.as_os_str().is_empty() || !path.exists() {
+        return Err(format!("Arquivo não encontrado: {}", path.display()));
     }
 
-    // Salvar histórico
-    let json_str = serde_json::to_string_pretty(&history)
-        .map_err(|e| format!("Erro ao serializar histórico: {}", e))?;
+    Ok((entry, path))
+}
 
-    fs::write(&history_path, json_str).map_err(|e| format!("Erro ao salvar histórico: {}", e))?;
+// Verificar quais atividades apontam para arquivos que não existem mais
+#[tauri::command]
+fn check_history_files() -> Result<String, String> {
+    let missing: Vec<Value> = history::missing_files()?
+        .into_iter()
+        .map(|(id, path)| json!({ "id": id, "file_path": path }))
+        .collect();
 
-    Ok(())
+    Ok(json!({ "missing": missing }).to_string())
 }
 
-// Organização automática por data/tipo
-// Função preparada para uso futuro quando a organização automática for ativada
-#[allow(dead_code)]
-fn organize_file_by_date_type(file_path: &PathBuf, activity_type: &str) -> Result<PathBuf, String> {
-    let file_name = file_path
-        .file_name()
-        .ok_or("Nome de arquivo inválido")?
-        .to_str()
-        .ok_or("Erro ao converter nome do arquivo")?;
+// Remover atividade do histórico, opcionalmente apagando o arquivo
+#[tauri::command]
+fn delete_history_entry(id: String, delete_file: Option<bool>) -> Result<String, String> {
+    let entry = history::get(&id)?.ok_or_else(|| format!("Atividade não encontrada: {}", id))?;
+    let path = PathBuf::from(entry["file_path"].as_str().unwrap_or(""));
 
-    // Obter data atual
-    let now = chrono::Local::now();
-    let date_folder = now.format("%Y-%m-%d").to_string();
-
-    // Determinar tipo de arquivo
-    let file_type = if let Some(ext) = file_path.extension().and_then(|e| e.to_str()) {
-        let ext_lower = ext.to_lowercase();
-        if ["mp3", "wav", "ogg", "flac", "aac", "m4a"].contains(&ext_lower.as_str()) {
-            "audio"
-        } else if ["mp4", "avi", "mkv", "mov", "webm", "flv"].contains(&ext_lower.as_str()) {
-            "video"
-        } else if ["jpg", "jpeg", "png", "gif", "bmp", "webp"].contains(&ext_lower.as_str()) {
-            "image"
-        } else if ["pdf", "doc", "docx", "txt"].contains(&ext_lower.as_str()) {
-            "document"
-        } else {
-            "other"
-        }
+    let file_deleted = if delete_file.unwrap_or(false) && path.is_file() {
+        fs::remove_file(&path).map_err(|e| format!("Erro ao apagar {}: {}", path.display(), e))?;
+        log_info(&format!(
+            "Arquivo apagado pelo histórico: {}",
+            path.display()
+        ));
+        true
     } else {
-        "other"
+        false
     };
 
-    // Determinar pasta base baseado no tipo de atividade
-    let base_dir = match activity_type {
-        "youtube_download" => get_videos_path()?,
-        "whatsapp_receive" | "upload" => get_uploads_path()?,
-        _ => get_uploads_path()?,
-    };
+    history::delete(&id)?;
 
-    // Criar estrutura: base/YYYY-MM-DD/tipo/origem/arquivo
-    let organized_path = base_dir
-        .join(&date_folder)
-        .join(file_type)
-        .join(activity_type)
-        .join(file_name);
-
-    // Criar diretórios necessários
-    if let Some(parent) = organized_path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Erro ao criar diretório organizado: {}", e))?;
-    }
-
-    // Mover arquivo se necessário (se já existe em outro lugar)
-    if file_path.exists() && file_path != &organized_path {
-        // Verificar se arquivo de destino já existe
-        let mut final_path = organized_path.clone();
-        let mut counter = 1;
-
-        while final_path.exists() {
-            let stem = final_path
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("file");
-            let extension = final_path
-                .extension()
-                .and_then(|s| s.to_str())
-                .unwrap_or("");
-
-            let new_filename = if extension.is_empty() {
-                format!("{} ({})", stem, counter)
-            } else {
-                format!("{} ({}).{}", stem, counter, extension)
-            };
-
-            final_path = organized_path.parent().unwrap().join(&new_filename);
-            counter += 1;
-        }
+    Ok(json!({ "deleted": true, "fileDeleted": file_deleted }).to_string())
+}
 
-        fs::rename(file_path, &final_path).map_err(|e| format!("Erro ao mover arquivo: {}", e))?;
+// Renomear o arquivo de uma atividade mantendo histórico e índice sincronizados
+#[tauri::command]
+fn rename_history_file(id: String, new_name: String) -> Result<String, String> {
+    let (_, path) = history_file(&id)?;
 
-        Ok(final_path)
-    } else {
-        Ok(organized_path)
+    let new_name = new_name.trim();
+    if new_name.is_empty() || new_name.contains(['/', '\\']) || new_name == "." || new_name == ".."
+    {
+        return Err(format!("Nome de arquivo inválido: {}", new_name));
+    }
+    // Mesmas regras dos nomes gerados: caracteres inválidos e nomes reservados do Windows
+    let new_name = filenames::sanitize_file_name(new_name);
+
+    let target = path
+        .parent()
+        .ok_or("Pasta do arquivo não encontrada")?
+        .join(&new_name);
+    if target == path {
+        return Ok(json!({ "file_path": path.display().to_string() }).to_string());
     }
+    if target.exists() {
+        return Err(format!("Já existe um arquivo chamado {}", new_name));
+    }
+
+    fs::rename(&path, &target).map_err(|e| format!("Erro ao renomear arquivo: {}", e))?;
+
+    let moves = vec![(path, target.clone())];
+    history::update_paths(&moves)?;
+    content_index::update_paths(&moves);
+
+    Ok(json!({ "file_path": target.display().to_string() }).to_string())
 }
 
-// Obter histórico de atividades
+// Abrir o arquivo de uma atividade no aplicativo padrão
 #[tauri::command]
-fn get_activity_history(
-    limit: Option<usize>,
-    activity_type: Option<String>,
-) -> Result<String, String> {
-    let history_path = get_history_file_path();
+fn open_history_file(id: String, app: tauri::AppHandle) -> Result<(), String> {
+    let (_, path) = history_file(&id)?;
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("Erro ao abrir arquivo: {}", e))
+}
 
-    if !history_path.exists() {
-        return Ok(json!([]).to_string());
-    }
+// Mostrar o arquivo de uma atividade na pasta
+#[tauri::command]
+fn reveal_history_file(id: String, app: tauri::AppHandle) -> Result<(), String> {
+    let (_, path) = history_file(&id)?;
+    app.opener()
+        .reveal_item_in_dir(&path)
+        .map_err(|e| format!("Erro ao mostrar arquivo: {}", e))
+}
 
-    let content =
-        fs::read_to_string(&history_path).map_err(|e| format!("Erro ao ler histórico: {}", e))?;
-
-    let mut history: Vec<Value> =
-        serde_json::from_str(&content).map_err(|e| format!("Erro ao parsear histórico: {}", e))?;
-
-    // Filtrar por tipo se especificado
-    if let Some(filter_type) = activity_type {
-        history.retain(|item| {
-            item.get("type")
-                .and_then(|v| v.as_str())
-                .map(|t| t == filter_type)
-                .unwrap_or(false)
-        });
+// Remover em segundo plano as atividades cujo arquivo sumiu
+#[tauri::command]
+fn prune_missing_history() -> Result<String, String> {
+    if !history::start_prune() {
+        return Err("Limpeza do histórico já está em andamento".to_string());
     }
 
-    // Aplicar limite
-    let limit_value = limit.unwrap_or(100);
-    history.truncate(limit_value);
+    Ok(json!({ "started": true }).to_string())
+}
 
-    Ok(json!(history).to_string())
+#[tauri::command]
+fn get_history_prune_status() -> Result<String, String> {
+    Ok(history::prune_status().to_string())
 }
 
-// Obter estatísticas
+// Reconstruir índice de duplicados a partir das pastas de uploads e vídeos
 #[tauri::command]
-fn get_statistics() -> Result<String, String> {
-    let history_path = get_history_file_path();
-
-    let mut stats = json!({
-        "total_activities": 0,
-        "total_size": 0,
-        "by_type": {},
-        "by_date": {},
-        "recent_activities": []
-    });
+fn rebuild_content_index() -> Result<String, String> {
+    let folders = vec![get_uploads_path()?, get_videos_path()?];
+    let (videos, files) = content_index::rebuild(&folders)?;
 
-    if history_path.exists() {
-        if let Ok(content) = fs::read_to_string(&history_path) {
-            if let Ok(history) = serde_json::from_str::<Vec<Value>>(&content) {
-                let mut by_type: std::collections::HashMap<String, i64> =
-                    std::collections::HashMap::new();
-                let mut by_date: std::collections::HashMap<String, i64> =
-                    std::collections::HashMap::new();
-                let mut total_size: u64 = 0;
-
-                for activity in &history {
-                    // Contar por tipo
-                    if let Some(type_str) = activity.get("type").and_then(|v| v.as_str()) {
-                        *by_type.entry(type_str.to_string()).or_insert(0) += 1;
-                    }
+    Ok(json!({ "videos": videos, "files": files }).to_string())
+}
 
-                    // Contar por data
-                    if let Some(date_str) = activity.get("date").and_then(|v| v.as_str()) {
-                        let date_only = date_str.split(' ').next().unwrap_or(date_str);
-                        *by_date.entry(date_only.to_string()).or_insert(0) += 1;
-                    }
+// Obter estatísticas do período (7d, 30d, 365d, all ou custom), agrupadas por dia, semana ou mês
+#[tauri::command]
+fn get_statistics(
+    period: Option<String>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+    bucket: Option<String>,
+) -> Result<String, String> {
+    let window = stats::parse_window(period.as_deref(), date_from.as_deref(), date_to.as_deref())?;
+    let bucket = stats::parse_bucket(bucket.as_deref())?;
+    let stats = stats::compute(window, bucket)?;
 
-                    // Somar tamanhos
-                    if let Some(size) = activity.get("file_size").and_then(|v| v.as_u64()) {
-                        total_size += size;
-                    }
-                }
+    Ok(stats.to_string())
+}
 
-                // Pegar últimas 10 atividades
-                let recent: Vec<&Value> = history.iter().take(10).collect();
+// Exportar histórico filtrado como CSV ou JSON na pasta escolhida
+#[tauri::command]
+fn export_history(
+    folder: String,
+    format: String,
+    query: Option<history::HistoryQuery>,
+) -> Result<String, String> {
+    let format = report::ExportFormat::parse(&format)?;
+    let (path, count) =
+        report::export_history(&PathBuf::from(folder), format, &query.unwrap_or_default())?;
 
-                stats = json!({
-                    "total_activities": history.len(),
-                    "total_size": total_size,
-                    "by_type": by_type,
-                    "by_date": by_date,
-                    "recent_activities": recent
-                });
-            }
-        }
+    Ok(json!({ "path": path.display().to_string(), "count": count }).to_string())
+}
+
+// Gerar relatório HTML do período com totais, gráficos e lista de arquivos
+#[tauri::command]
+fn generate_report(
+    folder: String,
+    period: Option<String>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+    bucket: Option<String>,
+) -> Result<String, String> {
+    let window = stats::parse_window(period.as_deref(), date_from.as_deref(), date_to.as_deref())?;
+    let bucket = stats::parse_bucket(bucket.as_deref())?;
+    let path = report::generate_report(&PathBuf::from(folder), window, bucket)?;
+
+    Ok(json!({ "path": path.display().to_string() }).to_string())
+}
+
+// Simular as regras de retenção sem apagar ou mover nada
+#[tauri::command]
+fn preview_retention() -> Result<String, String> {
+    let actions = retention::plan();
+    let bytes: u64 = actions.iter().map(|a| a.size).sum();
+
+    Ok(json!({ "actions": actions, "bytes": bytes }).to_string())
+}
+
+// Aplicar as regras de retenção agora
+#[tauri::command]
+fn run_retention() -> Result<String, String> {
+    let (done, errors) = retention::run()?;
+    let bytes: u64 = done.iter().map(|a| a.size).sum();
+
+    Ok(json!({ "actions": done, "bytes": bytes, "errors": errors }).to_string())
+}
+
+// Ações já executadas pela retenção, mais recentes primeiro
+#[tauri::command]
+fn get_retention_log(limit: Option<usize>) -> Result<String, String> {
+    let entries = history::retention_audit(limit.unwrap_or(100))?;
+    Ok(json!(entries).to_string())
+}
+
+// Iniciar backup das pastas de uploads e vídeos para o destino configurado
+#[tauri::command]
+fn run_backup() -> Result<String, String> {
+    if !backup::start_run() {
+        return Err("Backup já está em andamento".to_string());
     }
 
-    Ok(stats.to_string())
+    Ok(json!({ "started": true }).to_string())
+}
+
+#[tauri::command]
+fn get_backup_status() -> Result<String, String> {
+    Ok(backup::status().to_string())
+}
+
+// Execuções de backup registradas no destino (sem a lista de arquivos)
+#[tauri::command]
+fn list_backups() -> Result<String, String> {
+    let runs: Vec<Value> = backup::list()?
+        .iter()
+        .map(|m| {
+            json!({
+                "id": m.id,
+                "startedAt": m.started_at,
+                "finishedAt": m.finished_at,
+                "files": m.files.len(),
+                "copied": m.copied,
+                "unchanged": m.unchanged,
+                "errors": m.errors.len(),
+                "verified": m.verified
+            })
+        })
+        .collect();
+
+    Ok(json!(runs).to_string())
+}
+
+// Arquivos de uma execução de backup (a mais recente, se nenhuma for indicada)
+#[tauri::command]
+fn list_backup_files(run_id: Option<String>) -> Result<String, String> {
+    let manifests = backup::list()?;
+    let manifest = match run_id.as_deref() {
+        Some(id) => manifests.iter().find(|m| m.id == id),
+        None => manifests.first(),
+    }
+    .ok_or("Backup não encontrado")?;
+
+    Ok(json!(manifest.files).to_string())
+}
+
+#[tauri::command]
+fn verify_backup(run_id: Option<String>) -> Result<String, String> {
+    let (manifest, problems) = backup::verify(run_id.as_deref())?;
+
+    Ok(
+        json!({ "id": manifest.id, "verified": problems.is_empty(), "problems": problems })
+            .to_string(),
+    )
+}
+
+// Restaurar um arquivo do backup para a pasta de origem
+#[tauri::command]
+fn restore_backup_file(
+    run_id: Option<String>,
+    folder: String,
+    path: String,
+) -> Result<String, String> {
+    let target = backup::restore(run_id.as_deref(), &folder, &path)?;
+
+    Ok(json!({ "path": target.display().to_string() }).to_string())
 }
 
 // Obter logs do sistema
 #[tauri::command]
 fn get_system_logs(limit: Option<usize>) -> Result<String, String> {
-    let app_data_dir =
-        dirs::data_local_dir().ok_or("Não foi possível encontrar diretório de dados")?;
+    let log_file = log::LogFile::System
+        .path()
+        .ok_or("Não foi possível encontrar diretório de dados")?;
+    log::flush();
+
+    // Últimas linhas, mais recentes primeiro, no formato legível
+    let recent_lines: Vec<String> = log::tail(&log_file, limit.unwrap_or(100))
+        .into_iter()
+        .map(|line| match log::Record::parse(&line) {
+            Some(record) => record.display_line(),
+            None => line,
+        })
+        .collect();
+
+    Ok(json!(recent_lines).to_string())
+}
 
-    let logs_dir = app_data_dir.join("UploadIASD").join("logs");
-    fs::create_dir_all(&logs_dir).map_err(|e| format!("Erro ao criar diretório de logs: {}", e))?;
+// Consultar logs por nível, origem, período e texto, com paginação
+#[tauri::command]
+fn query_logs(query: Option<log_query::LogQuery>) -> Result<String, String> {
+    let page = log_query::query(&query.unwrap_or_default())?;
 
-    let log_file = logs_dir.join("system.log");
+    Ok(page.to_string())
+}
 
-    let logs_content = if log_file.exists() {
-        fs::read_to_string(&log_file).unwrap_or_else(|_| String::new())
-    } else {
-        String::new()
-    };
+// Acompanhar os logs ao vivo: eventos "log-record" com o filtro aplicado no backend
+#[tauri::command]
+fn subscribe_logs(
+    filter: Option<log_query::LogFilter>,
+    app: tauri::AppHandle,
+    window: tauri::Window,
+) -> Result<String, String> {
+    let id = log_stream::subscribe(app, window.label(), filter.unwrap_or_default())?;
 
-    // Processar logs (últimas linhas - mais recentes primeiro)
-    let lines: Vec<&str> = logs_content.lines().collect();
-    let limit_value = limit.unwrap_or(100);
-    // Pegar as últimas linhas e inverter para mostrar as mais recentes primeiro
-    let recent_lines: Vec<&str> = lines.iter().rev().take(limit_value).copied().collect();
+    Ok(json!({ "subscriptionId": id }).to_string())
+}
 
-    Ok(json!(recent_lines).to_string())
+#[tauri::command]
+fn unsubscribe_logs(subscription_id: String) -> Result<String, String> {
+    let removed = log_stream::unsubscribe(&subscription_id)?;
+
+    Ok(json!({ "removed": removed }).to_string())
 }
 
 #[tauri::command]
@@ -1590,14 +2334,20 @@ fn log_event(
             (!trimmed.is_empty()).then(|| trimmed.to_string())
         })
         .unwrap_or_else(|| "INFO".to_string());
-    let mut payload = message;
+    // Contexto em JSON vira campos do registro; texto simples fica em "context"
+    let mut fields = serde_json::Map::new();
     if let Some(ctx) = context {
         let trimmed = ctx.trim();
         if !trimmed.is_empty() {
-            payload = format!("{} | {}", payload, trimmed);
+            match serde_json::from_str::<Value>(trimmed) {
+                Ok(Value::Object(map)) => fields = map,
+                _ => {
+                    fields.insert("context".to_string(), json!(trimmed));
+                }
+            }
         }
     }
-    log_message(&level, &payload);
+    log::log_record(&level, "ui", &message, fields);
     Ok(())
 }
 
@@ -1790,13 +2540,34 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::default().build())
+        .manage(settings::shared())
+        // Recarregar ou fechar a janela encerra as assinaturas de logs dela
+        .on_page_load(|webview, payload| {
+            if payload.event() == tauri::webview::PageLoadEvent::Started {
+                log_stream::unsubscribe_window(webview.label());
+            }
+        })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                log_stream::unsubscribe_window(window.label());
+            }
+        })
         .setup(|_app| {
+            folders::report_startup();
+            scheduler::start();
+            retention::start();
+            backup::start();
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             greet,
             get_video_info,
             download_youtube,
+            export_transcript,
+            list_schedules,
+            save_schedule,
+            delete_schedule,
+            run_schedule_now,
             start_whatsapp_bot,
             stop_whatsapp_bot,
             get_whatsapp_qr,
@@ -1804,14 +2575,50 @@ pub fn run() {
             start_upload_server,
             stop_upload_server,
             get_upload_server_url,
-            get_store_path,
             set_uploads_path,
             set_videos_path,
+            get_settings,
+            update_settings,
+            export_settings,
+            import_settings,
+            reset_settings,
+            get_folder_status,
+            use_default_folder,
+            preview_route,
+            reorganize_folders,
+            undo_reorganization,
+            list_reorganizations,
+            set_youtube_cookies,
+            clear_youtube_cookies,
+            get_youtube_cookies_status,
             check_for_updates,
             get_app_version,
             get_activity_history,
+            query_activity_history,
+            check_history_files,
+            delete_history_entry,
+            rename_history_file,
+            open_history_file,
+            reveal_history_file,
+            prune_missing_history,
+            get_history_prune_status,
+            rebuild_content_index,
             get_statistics,
+            export_history,
+            generate_report,
+            preview_retention,
+            run_retention,
+            get_retention_log,
+            run_backup,
+            get_backup_status,
+            list_backups,
+            list_backup_files,
+            verify_backup,
+            restore_backup_file,
             get_system_logs,
+            query_logs,
+            subscribe_logs,
+            unsubscribe_logs,
             log_event,
             open_link
         ])
@@ -1,63 +1,455 @@
-use chrono::Local;
-use dirs;
+use chrono::{DateTime, FixedOffset, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f%:z";
+pub const DEFAULT_MAX_FILE_MB: u32 = 5;
+pub const DEFAULT_KEEP_FILES: u32 = 10;
+// Registros guardados para assinantes lentos antes de descartar
+const BROADCAST_CAPACITY: usize = 1024;
+
+// Um registro por linha (JSON) nos arquivos de log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub timestamp: String,
+    pub level: String,
+    pub module: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub context: Map<String, Value>,
+}
+
+impl Record {
+    // Linha legível no formato antigo: "[data] [NÍVEL] mensagem"
+    pub fn display_line(&self) -> String {
+        let timestamp = self
+            .time()
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| self.timestamp.clone());
+        if self.context.is_empty() {
+            format!("[{}] [{}] {}", timestamp, self.level, self.message)
+        } else {
+            format!(
+                "[{}] [{}] {} | {}",
+                timestamp,
+                self.level,
+                self.message,
+                Value::Object(self.context.clone())
+            )
+        }
+    }
+
+    // Aceita JSON e também as linhas antigas em texto, de antes da rotação
+    pub fn parse(line: &str) -> Option<Record> {
+        if let Ok(record) = serde_json::from_str::<Record>(line) {
+            return Some(record);
+        }
+
+        let rest = line.strip_prefix('[')?;
+        let (timestamp, rest) = rest.split_once("] [")?;
+        let (level, message) = rest.split_once("] ")?;
+        let timestamp = chrono::NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S")
+            .ok()?
+            .and_local_timezone(Local)
+            .single()?;
+
+        Some(Record {
+            timestamp: timestamp.format(TIMESTAMP_FORMAT).to_string(),
+            level: level.to_string(),
+            module: "app".to_string(),
+            message: message.to_string(),
+            context: Map::new(),
+        })
+    }
+
+    pub fn time(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_str(&self.timestamp, TIMESTAMP_FORMAT).ok()
+    }
+
+    pub fn is_issue(&self) -> bool {
+        self.level == "WARN" || self.level == "ERROR"
+    }
+}
+
+// Rotação: por tamanho e a cada dia, mantendo um número limitado de arquivos antigos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LoggingSettings {
+    pub max_file_mb: u32,
+    pub keep_files: u32,
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        LoggingSettings {
+            max_file_mb: DEFAULT_MAX_FILE_MB,
+            keep_files: DEFAULT_KEEP_FILES,
+        }
+    }
+}
+
+impl LoggingSettings {
+    pub fn validate(&mut self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !(1..=100).contains(&self.max_file_mb) {
+            problems.push("Tamanho máximo do log deve estar entre 1 e 100 MB".to_string());
+            self.max_file_mb = DEFAULT_MAX_FILE_MB;
+        }
+        if !(1..=100).contains(&self.keep_files) {
+            problems.push("Quantidade de logs antigos deve estar entre 1 e 100".to_string());
+            self.keep_files = DEFAULT_KEEP_FILES;
+        }
+        problems
+    }
+}
+
+// Arquivos de log: todos os registros ou apenas avisos e erros
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFile {
+    System,
+    Issues,
+}
+
+impl LogFile {
+    fn stem(self) -> &'static str {
+        match self {
+            LogFile::System => "system",
+            LogFile::Issues => "issues",
+        }
+    }
+
+    pub fn path(self) -> Option<PathBuf> {
+        Some(logs_dir_path()?.join(format!("{}.log", self.stem())))
+    }
+
+    // Arquivo atual seguido dos rotacionados, do mais novo para o mais antigo
+    pub fn files(self) -> Vec<PathBuf> {
+        let current = match self.path() {
+            Some(path) => path,
+            None => return Vec::new(),
+        };
+        let prefix = format!("{}-", self.stem());
+
+        let mut rotated: Vec<PathBuf> = current
+            .parent()
+            .and_then(|dir| fs::read_dir(dir).ok())
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| {
+                        let name = p.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                        name.starts_with(&prefix) && name.ends_with(".log")
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        // Nome contém a data/hora da rotação, então a ordem alfabética é cronológica
+        rotated.sort();
+        rotated.reverse();
+
+        let mut files = Vec::new();
+        if current.exists() {
+            files.push(current);
+        }
+        files.extend(rotated);
+        files
+    }
+}
 
 fn logs_dir_path() -> Option<PathBuf> {
     let app_data_dir = dirs::data_local_dir()?;
     Some(app_data_dir.join("UploadIASD").join("logs"))
 }
 
-fn system_log_file_path() -> Option<PathBuf> {
-    Some(logs_dir_path()?.join("system.log"))
+enum Command {
+    Write(Record),
+    Flush(Sender<()>),
 }
 
-fn issues_log_file_path() -> Option<PathBuf> {
-    Some(logs_dir_path()?.join("issues.log"))
+// Arquivo aberto pelo gravador, com a data usada na rotação diária
+struct OpenLog {
+    kind: LogFile,
+    writer: Option<BufWriter<fs::File>>,
+    size: u64,
+    date: NaiveDate,
 }
 
-fn append_line(log_path: &PathBuf, line: &str) {
-    if let Some(parent) = log_path.parent() {
-        let _ = fs::create_dir_all(parent);
+impl OpenLog {
+    fn new(kind: LogFile) -> Self {
+        OpenLog {
+            kind,
+            writer: None,
+            size: 0,
+            date: Local::now().date_naive(),
+        }
     }
-    if let Ok(mut file) = fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(log_path)
-    {
-        let _ = writeln!(file, "{}", line);
+
+    fn open(&mut self) -> Option<&mut BufWriter<fs::File>> {
+        if self.writer.is_none() {
+            let path = self.kind.path()?;
+            if let Some(parent) = path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .ok()?;
+            let metadata = file.metadata().ok();
+            self.size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+            self.date = metadata
+                .and_then(|m| m.modified().ok())
+                .map(|t| DateTime::<Local>::from(t).date_naive())
+                .unwrap_or_else(|| Local::now().date_naive());
+            self.writer = Some(BufWriter::new(file));
+        }
+        self.writer.as_mut()
+    }
+
+    fn rotate_if_needed(&mut self, config: &LoggingSettings) {
+        let today = Local::now().date_naive();
+        let too_big = self.size >= config.max_file_mb as u64 * 1024 * 1024;
+        if self.writer.is_none() || (!too_big && self.date == today) || self.size == 0 {
+            return;
+        }
+
+        if let Some(mut writer) = self.writer.take() {
+            let _ = writer.flush();
+        }
+        let current = match self.kind.path() {
+            Some(path) => path,
+            None => return,
+        };
+        let rotated = current.with_file_name(format!(
+            "{}-{}.log",
+            self.kind.stem(),
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let _ = fs::rename(&current, &rotated);
+
+        // Remover os mais antigos além do limite configurado
+        for old in self
+            .kind
+            .files()
+            .into_iter()
+            .filter(|p| *p != current)
+            .skip(config.keep_files as usize)
+        {
+            let _ = fs::remove_file(old);
+        }
+    }
+
+    fn write(&mut self, line: &str, config: &LoggingSettings) {
+        self.rotate_if_needed(config);
+        if let Some(writer) = self.open() {
+            if writeln!(writer, "{}", line).is_ok() {
+                self.size += line.len() as u64 + 1;
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(writer) = self.writer.as_mut() {
+            let _ = writer.flush();
+        }
     }
 }
 
-pub fn log_message(level: &str, message: &str) {
-    let normalized_level = if level.trim().is_empty() {
+// Gravador em segundo plano: grava em lote e descarrega quando a fila esvazia
+fn run_writer(receiver: Receiver<Command>) {
+    let mut system = OpenLog::new(LogFile::System);
+    let mut issues = OpenLog::new(LogFile::Issues);
+
+    while let Ok(command) = receiver.recv() {
+        let config = crate::settings::current().logging;
+        let mut pending = vec![command];
+        pending.extend(receiver.try_iter());
+
+        let mut waiting = Vec::new();
+        for command in pending {
+            match command {
+                Command::Write(record) => {
+                    let line = serde_json::to_string(&record).unwrap_or_default();
+                    system.write(&line, &config);
+                    if record.is_issue() {
+                        issues.write(&line, &config);
+                    }
+                }
+                Command::Flush(done) => waiting.push(done),
+            }
+        }
+
+        system.flush();
+        issues.flush();
+        for done in waiting {
+            let _ = done.send(());
+        }
+    }
+}
+
+fn writer() -> &'static Sender<Command> {
+    static WRITER: OnceLock<Sender<Command>> = OnceLock::new();
+    WRITER.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || run_writer(receiver));
+        sender
+    })
+}
+
+fn broadcaster() -> &'static broadcast::Sender<Record> {
+    static BROADCAST: OnceLock<broadcast::Sender<Record>> = OnceLock::new();
+    BROADCAST.get_or_init(|| broadcast::channel(BROADCAST_CAPACITY).0)
+}
+
+// Receber os novos registros à medida que são gerados
+pub fn subscribe() -> broadcast::Receiver<Record> {
+    broadcaster().subscribe()
+}
+
+// Aguardar a gravação dos registros pendentes (antes de ler os arquivos)
+pub fn flush() {
+    let (done, wait) = mpsc::channel();
+    if writer().send(Command::Flush(done)).is_ok() {
+        let _ = wait.recv_timeout(Duration::from_secs(2));
+    }
+}
+
+// Módulo de origem a partir do arquivo que chamou (src/upload_server.rs -> upload_server)
+fn module_name(file: &str) -> String {
+    match Path::new(file).file_stem().and_then(|s| s.to_str()) {
+        Some("lib") | None => "app".to_string(),
+        Some(stem) => stem.to_string(),
+    }
+}
+
+fn normalize_level(level: &str) -> String {
+    if level.trim().is_empty() {
         "INFO".to_string()
     } else {
         level.trim().to_uppercase()
+    }
+}
+
+pub fn log_record(level: &str, module: &str, message: &str, context: Map<String, Value>) {
+    let record = Record {
+        timestamp: Local::now().format(TIMESTAMP_FORMAT).to_string(),
+        level: normalize_level(level),
+        module: module.to_string(),
+        message: message.to_string(),
+        context,
     };
-    let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
-    let line = format!("[{}] [{}] {}", timestamp, normalized_level, message);
 
-    if let Some(system_log_path) = system_log_file_path() {
-        append_line(&system_log_path, &line);
+    // Só copiar o registro quando alguém acompanha os logs ao vivo
+    if broadcaster().receiver_count() > 0 {
+        let _ = broadcaster().send(record.clone());
     }
+    let _ = writer().send(Command::Write(record));
+}
 
-    if normalized_level == "WARN" || normalized_level == "ERROR" {
-        if let Some(issues_log_path) = issues_log_file_path() {
-            append_line(&issues_log_path, &line);
-        }
-    }
+#[track_caller]
+pub fn log_message(level: &str, message: &str) {
+    let module = module_name(std::panic::Location::caller().file());
+    log_record(level, &module, message, Map::new());
 }
 
+#[track_caller]
 pub fn log_info(message: &str) {
     log_message("INFO", message);
 }
 
+#[track_caller]
 pub fn log_warn(message: &str) {
     log_message("WARN", message);
 }
 
+#[track_caller]
 pub fn log_error(message: &str) {
     log_message("ERROR", message);
 }
+
+const CHUNK: u64 = 64 * 1024;
+
+// Linhas do arquivo do fim para o começo, lendo blocos sob demanda
+pub struct ReverseLines {
+    file: fs::File,
+    position: u64,
+    partial: Vec<u8>,
+    ready: Vec<String>,
+}
+
+impl ReverseLines {
+    pub fn open(path: &Path) -> Option<Self> {
+        let mut file = fs::File::open(path).ok()?;
+        let position = file.seek(SeekFrom::End(0)).ok()?;
+        Some(ReverseLines {
+            file,
+            position,
+            partial: Vec::new(),
+            ready: Vec::new(),
+        })
+    }
+
+    fn read_chunk(&mut self) -> bool {
+        let read_size = CHUNK.min(self.position);
+        self.position -= read_size;
+
+        let mut buffer = vec![0u8; read_size as usize];
+        if self.file.seek(SeekFrom::Start(self.position)).is_err()
+            || self.file.read_exact(&mut buffer).is_err()
+        {
+            self.position = 0;
+            return false;
+        }
+        buffer.extend_from_slice(&self.partial);
+
+        // O primeiro pedaço pode ser uma linha incompleta; fica para a próxima leitura
+        let mut segments: Vec<&[u8]> = buffer.split(|b| *b == b'\n').collect();
+        self.partial = if self.position > 0 {
+            segments.remove(0).to_vec()
+        } else {
+            Vec::new()
+        };
+
+        // Guardadas em ordem do arquivo; `pop` devolve a mais recente
+        self.ready = segments
+            .into_iter()
+            .map(|s| {
+                String::from_utf8_lossy(s)
+                    .trim_end_matches('\r')
+                    .to_string()
+            })
+            .filter(|line| !line.is_empty())
+            .collect();
+        true
+    }
+}
+
+impl Iterator for ReverseLines {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            if let Some(line) = self.ready.pop() {
+                return Some(line);
+            }
+            if self.position == 0 || !self.read_chunk() {
+                return None;
+            }
+        }
+    }
+}
+
+// Últimas `limit` linhas do arquivo (mais recentes primeiro)
+pub fn tail(path: &Path, limit: usize) -> Vec<String> {
+    ReverseLines::open(path)
+        .map(|lines| lines.take(limit).collect())
+        .unwrap_or_default()
+}
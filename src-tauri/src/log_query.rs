@@ -0,0 +1,286 @@
+use crate::log::{self, LogFile, Record, ReverseLines};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+// Filtros dos registros de log; campos vazios não filtram
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogFilter {
+    // INFO, WARN, ERROR...; só WARN/ERROR consulta o issues.log
+    pub levels: Vec<String>,
+    // Módulo (ex.: upload_server, ui) ou prefixo da mensagem (ex.: [WA-BOT:stderr])
+    pub sources: Vec<String>,
+    // Data/hora local (AAAA-MM-DD, AAAA-MM-DD HH:MM[:SS]) ou RFC 3339
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub text: Option<String>,
+    // Interpretar `text` como expressão regular
+    pub regex: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogQuery {
+    #[serde(flatten)]
+    pub filter: LogFilter,
+    pub limit: Option<usize>,
+    // Cursor devolvido pela página anterior
+    pub cursor: Option<String>,
+}
+
+enum TextMatch {
+    Plain(String),
+    Pattern(Regex),
+}
+
+// Filtro validado, pronto para testar registros
+pub struct Matcher {
+    levels: Vec<String>,
+    sources: Vec<String>,
+    from: Option<DateTime<FixedOffset>>,
+    to: Option<DateTime<FixedOffset>>,
+    text: Option<TextMatch>,
+}
+
+fn parse_time(value: &str, end_of_day: bool) -> Result<DateTime<FixedOffset>, String> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time);
+    }
+
+    let naive = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .or_else(|| {
+        let day = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+        let time = if end_of_day {
+            NaiveTime::from_hms_milli_opt(23, 59, 59, 999)?
+        } else {
+            NaiveTime::MIN
+        };
+        Some(day.and_time(time))
+    })
+    .ok_or_else(|| format!("Data/hora inválida: {}. Use AAAA-MM-DD HH:MM.", value))?;
+
+    naive
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|t| t.fixed_offset())
+        .ok_or_else(|| format!("Data/hora inválida: {}", value))
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|v| !v.is_empty())
+}
+
+impl LogFilter {
+    pub fn compile(&self) -> Result<Matcher, String> {
+        let levels: Vec<String> = self
+            .levels
+            .iter()
+            .map(|l| l.trim().to_uppercase())
+            .filter(|l| !l.is_empty())
+            .collect();
+        let sources: Vec<String> = self
+            .sources
+            .iter()
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        let from = non_empty(self.from.as_deref())
+            .map(|v| parse_time(v, false))
+            .transpose()?;
+        let to = non_empty(self.to.as_deref())
+            .map(|v| parse_time(v, true))
+            .transpose()?;
+        if let (Some(from), Some(to)) = (from, to) {
+            if from > to {
+                return Err("Data inicial posterior à data final".to_string());
+            }
+        }
+
+        let text = match non_empty(self.text.as_deref()) {
+            Some(pattern) if self.regex => Some(TextMatch::Pattern(
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .size_limit(1 << 20)
+                    .build()
+                    .map_err(|e| format!("Expressão regular inválida: {}", e))?,
+            )),
+            Some(text) => Some(TextMatch::Plain(text.to_lowercase())),
+            None => None,
+        };
+
+        Ok(Matcher {
+            levels,
+            sources,
+            from,
+            to,
+            text,
+        })
+    }
+}
+
+impl Matcher {
+    // Avisos e erros também ficam no issues.log, bem menor
+    fn log_file(&self) -> LogFile {
+        let only_issues =
+            !self.levels.is_empty() && self.levels.iter().all(|l| l == "WARN" || l == "ERROR");
+        if only_issues {
+            LogFile::Issues
+        } else {
+            LogFile::System
+        }
+    }
+
+    pub fn matches(&self, record: &Record) -> bool {
+        if !self.levels.is_empty() && !self.levels.contains(&record.level) {
+            return false;
+        }
+
+        if !self.sources.is_empty() {
+            let module = record.module.to_lowercase();
+            let message = record.message.to_lowercase();
+            if !self
+                .sources
+                .iter()
+                .any(|s| *s == module || message.starts_with(s.as_str()))
+            {
+                return false;
+            }
+        }
+
+        if self.from.is_some() || self.to.is_some() {
+            let time = match record.time() {
+                Some(time) => time,
+                None => return false,
+            };
+            if self.from.is_some_and(|from| time < from) || self.to.is_some_and(|to| time > to) {
+                return false;
+            }
+        }
+
+        match &self.text {
+            Some(TextMatch::Plain(text)) => {
+                record.message.to_lowercase().contains(text.as_str())
+                    || (!record.context.is_empty()
+                        && Value::Object(record.context.clone())
+                            .to_string()
+                            .to_lowercase()
+                            .contains(text.as_str()))
+            }
+            Some(TextMatch::Pattern(pattern)) => {
+                pattern.is_match(&record.message)
+                    || (!record.context.is_empty()
+                        && pattern.is_match(&Value::Object(record.context.clone()).to_string()))
+            }
+            None => true,
+        }
+    }
+}
+
+// Cursor: JSON [horário do último registro, quantos registros com esse horário já passaram]
+fn parse_cursor(cursor: &str) -> Result<(DateTime<FixedOffset>, usize), String> {
+    let invalid = || "Cursor de paginação inválido".to_string();
+    let parsed: Value = serde_json::from_str(cursor).map_err(|_| invalid())?;
+
+    let time = parsed[0]
+        .as_str()
+        .and_then(|t| DateTime::parse_from_str(t, log::TIMESTAMP_FORMAT).ok())
+        .ok_or_else(invalid)?;
+    let seen = parsed[1].as_u64().ok_or_else(invalid)? as usize;
+
+    Ok((time, seen))
+}
+
+// Registros mais recentes primeiro, percorrendo o arquivo atual e os rotacionados
+pub fn query(query: &LogQuery) -> Result<Value, String> {
+    let matcher = query.filter.compile()?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let cursor = non_empty(query.cursor.as_deref())
+        .map(parse_cursor)
+        .transpose()?;
+    let log_file = matcher.log_file();
+
+    log::flush();
+
+    let mut items: Vec<Value> = Vec::new();
+    let mut more = false;
+    // Horário do registro atual e quantos já passaram com o mesmo horário
+    let mut position: Option<(DateTime<FixedOffset>, usize)> = None;
+    let mut next_cursor = None;
+
+    'files: for path in log_file.files() {
+        let lines = match ReverseLines::open(&path) {
+            Some(lines) => lines,
+            None => continue,
+        };
+
+        for line in lines {
+            let record = match Record::parse(&line) {
+                Some(record) => record,
+                None => continue,
+            };
+            let time = match record.time() {
+                Some(time) => time,
+                None => continue,
+            };
+
+            // Do mais novo para o mais antigo: antes do início do período, acabou
+            if matcher.from.is_some_and(|from| time < from) {
+                break 'files;
+            }
+
+            let seen = match position {
+                Some((last, seen)) if last == time => seen + 1,
+                _ => 1,
+            };
+            position = Some((time, seen));
+
+            // Pular o que já foi devolvido nas páginas anteriores
+            if let Some((cursor_time, cursor_seen)) = cursor {
+                if time > cursor_time || (time == cursor_time && seen <= cursor_seen) {
+                    continue;
+                }
+            }
+
+            if !matcher.matches(&record) {
+                continue;
+            }
+            if items.len() == limit {
+                more = true;
+                break 'files;
+            }
+
+            next_cursor =
+                Some(json!([time.format(log::TIMESTAMP_FORMAT).to_string(), seen]).to_string());
+            let mut item = serde_json::to_value(&record).unwrap_or(Value::Null);
+            item["line"] = json!(record.display_line());
+            items.push(item);
+        }
+    }
+
+    Ok(json!({
+        "file": match log_file {
+            LogFile::System => "system",
+            LogFile::Issues => "issues",
+        },
+        "limit": limit,
+        "items": items,
+        "nextCursor": if more { next_cursor } else { None }
+    }))
+}
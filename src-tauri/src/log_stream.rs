@@ -0,0 +1,121 @@
+use crate::log;
+use crate::log_query::LogFilter;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast::error::RecvError;
+
+pub const RECORD_EVENT: &str = "log-record";
+pub const LAGGED_EVENT: &str = "log-lagged";
+
+// Limite por janela, caso a interface esqueça de cancelar assinaturas antigas
+const MAX_SUBSCRIPTIONS_PER_WINDOW: usize = 8;
+
+struct Subscription {
+    window: String,
+    task: JoinHandle<()>,
+}
+
+// Assinaturas ativas: id -> janela dona e tarefa que repassa os registros
+static SUBSCRIPTIONS: Mutex<Option<HashMap<String, Subscription>>> = Mutex::new(None);
+
+// Repassar para a interface, como eventos, os novos registros que passam no filtro
+pub fn subscribe(app: AppHandle, window: &str, filter: LogFilter) -> Result<String, String> {
+    let matcher = filter.compile()?;
+
+    let mut guard = SUBSCRIPTIONS
+        .lock()
+        .map_err(|_| "Erro ao registrar assinatura de logs".to_string())?;
+    let subscriptions = guard.get_or_insert_with(HashMap::new);
+    if subscriptions
+        .values()
+        .filter(|s| s.window == window)
+        .count()
+        >= MAX_SUBSCRIPTIONS_PER_WINDOW
+    {
+        return Err(format!(
+            "Limite de {} assinaturas de logs por janela atingido",
+            MAX_SUBSCRIPTIONS_PER_WINDOW
+        ));
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let mut receiver = log::subscribe();
+
+    let subscription_id = id.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        // Nada de log aqui dentro: o registro voltaria para esta mesma assinatura
+        loop {
+            match receiver.recv().await {
+                Ok(record) => {
+                    if !matcher.matches(&record) {
+                        continue;
+                    }
+                    let mut payload = serde_json::to_value(&record).unwrap_or_default();
+                    payload["subscriptionId"] = json!(subscription_id);
+                    payload["line"] = json!(record.display_line());
+                    let _ = app.emit(RECORD_EVENT, payload);
+                }
+                // Interface não acompanhou o ritmo; avisar quantos registros se perderam
+                Err(RecvError::Lagged(skipped)) => {
+                    let _ = app.emit(
+                        LAGGED_EVENT,
+                        json!({ "subscriptionId": subscription_id, "skipped": skipped }),
+                    );
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
+    subscriptions.insert(
+        id.clone(),
+        Subscription {
+            window: window.to_string(),
+            task,
+        },
+    );
+
+    Ok(id)
+}
+
+pub fn unsubscribe(id: &str) -> Result<bool, String> {
+    let task = SUBSCRIPTIONS
+        .lock()
+        .map_err(|_| "Erro ao cancelar assinatura de logs".to_string())?
+        .as_mut()
+        .and_then(|subscriptions| subscriptions.remove(id));
+
+    Ok(match task {
+        Some(subscription) => {
+            subscription.task.abort();
+            true
+        }
+        None => false,
+    })
+}
+
+// Janela recarregada ou fechada: as assinaturas dela não têm mais quem as cancele
+pub fn unsubscribe_window(window: &str) -> usize {
+    let mut guard = match SUBSCRIPTIONS.lock() {
+        Ok(guard) => guard,
+        Err(_) => return 0,
+    };
+    let subscriptions = match guard.as_mut() {
+        Some(subscriptions) => subscriptions,
+        None => return 0,
+    };
+
+    let before = subscriptions.len();
+    subscriptions.retain(|_, subscription| {
+        if subscription.window == window {
+            subscription.task.abort();
+            false
+        } else {
+            true
+        }
+    });
+    before - subscriptions.len()
+}
@@ -0,0 +1,194 @@
+use crate::log::log_warn;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+pub const DEFAULT_RETRIES: u32 = 10;
+pub const DEFAULT_SOCKET_TIMEOUT_SECS: u32 = 20;
+pub const DEFAULT_PLAYER_CLIENT: &str = "android";
+
+const PROXY_SCHEMES: [&str; 6] = [
+    "http://",
+    "https://",
+    "socks4://",
+    "socks4a://",
+    "socks5://",
+    "socks5h://",
+];
+
+// Opções de rede aplicadas a todas as chamadas do yt-dlp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NetworkSettings {
+    // Limite de velocidade no formato do yt-dlp ("500K", "2M"); vazio = sem limite
+    pub rate_limit: Option<String>,
+    pub proxy: Option<String>,
+    // IP de origem ou nome da interface de rede
+    pub source_address: Option<String>,
+    pub retries: u32,
+    pub socket_timeout: u32,
+    // Cliente do YouTube usado pelo extrator; vazio = padrão do yt-dlp
+    pub player_client: String,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        NetworkSettings {
+            rate_limit: None,
+            proxy: None,
+            source_address: None,
+            retries: DEFAULT_RETRIES,
+            socket_timeout: DEFAULT_SOCKET_TIMEOUT_SECS,
+            player_client: DEFAULT_PLAYER_CLIENT.to_string(),
+        }
+    }
+}
+
+impl NetworkSettings {
+    // Argumentos globais do yt-dlp correspondentes a estas opções
+    pub fn yt_dlp_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "--retries".to_string(),
+            self.retries.to_string(),
+            "--fragment-retries".to_string(),
+            self.retries.to_string(),
+            "--socket-timeout".to_string(),
+            self.socket_timeout.to_string(),
+        ];
+
+        if let Some(rate) = &self.rate_limit {
+            args.push("--limit-rate".to_string());
+            args.push(rate.clone());
+        }
+
+        if let Some(proxy) = &self.proxy {
+            args.push("--proxy".to_string());
+            args.push(proxy.clone());
+        }
+
+        if let Some(source) = &self.source_address {
+            match resolve_source_address(source) {
+                Ok(ip) => {
+                    args.push("--source-address".to_string());
+                    args.push(ip.to_string());
+                }
+                Err(e) => log_warn(&e),
+            }
+        }
+
+        if !self.player_client.is_empty() {
+            args.push("--extractor-args".to_string());
+            args.push(format!("youtube:player_client={}", self.player_client));
+        }
+
+        args
+    }
+}
+
+// Normalizar limite de velocidade: número com sufixo opcional K, M ou G
+pub fn parse_rate_limit(value: &str) -> Result<Option<String>, String> {
+    let value = value.trim().to_uppercase();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    let value = value.strip_suffix("/S").unwrap_or(&value);
+    let value = value.strip_suffix('B').unwrap_or(value);
+    let (number, unit) = match value.chars().last() {
+        Some(c @ ('K' | 'M' | 'G')) => (&value[..value.len() - 1], c.to_string()),
+        _ => (value, String::new()),
+    };
+
+    // Só dígitos com fração opcional (nada de "inf", "1e3" ou sinal)
+    let number = number.trim();
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let plain_decimal = !whole.is_empty()
+        && whole.chars().all(|c| c.is_ascii_digit())
+        && fraction.chars().all(|c| c.is_ascii_digit())
+        && !(number.contains('.') && fraction.is_empty());
+
+    match number.parse::<f64>() {
+        Ok(n) if plain_decimal && n > 0.0 => Ok(Some(format!("{}{}", number, unit))),
+        _ => Err(format!(
+            "Limite de velocidade inválido: {}. Use valores como 500K ou 2M.",
+            value
+        )),
+    }
+}
+
+pub fn parse_proxy(value: &str) -> Result<Option<String>, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    let lower = value.to_lowercase();
+    if !PROXY_SCHEMES.iter().any(|scheme| lower.starts_with(scheme)) {
+        return Err(format!(
+            "Proxy inválido: {}. Use http://, https://, socks4:// ou socks5://",
+            value
+        ));
+    }
+
+    Ok(Some(value.to_string()))
+}
+
+pub fn parse_source_address(value: &str) -> Result<Option<String>, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    resolve_source_address(value)?;
+    Ok(Some(value.to_string()))
+}
+
+pub fn parse_player_client(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    let valid = value.split(',').all(|client| {
+        !client.is_empty()
+            && client
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    });
+
+    if value.is_empty() || valid {
+        Ok(value.to_string())
+    } else {
+        Err(format!("Cliente do YouTube inválido: {}", value))
+    }
+}
+
+pub fn validate_retries(retries: u32) -> Result<u32, String> {
+    if retries > 100 {
+        return Err("Número de tentativas deve estar entre 0 e 100".to_string());
+    }
+    Ok(retries)
+}
+
+pub fn validate_socket_timeout(seconds: u32) -> Result<u32, String> {
+    if !(1..=600).contains(&seconds) {
+        return Err("Tempo limite de conexão deve estar entre 1 e 600 segundos".to_string());
+    }
+    Ok(seconds)
+}
+
+// IP literal ou nome de interface (ex.: "Ethernet 2", "eth0"), preferindo IPv4
+fn resolve_source_address(value: &str) -> Result<IpAddr, String> {
+    if let Ok(ip) = value.parse::<IpAddr>() {
+        return Ok(ip);
+    }
+
+    let interfaces = local_ip_address::list_afinet_netifas()
+        .map_err(|e| format!("Erro ao listar interfaces de rede: {}", e))?;
+    let mut addresses: Vec<IpAddr> = interfaces
+        .into_iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case(value))
+        .map(|(_, ip)| ip)
+        .collect();
+    addresses.sort_by_key(|ip| !ip.is_ipv4());
+
+    addresses
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("Interface ou IP de origem não encontrado: {}", value))
+}
@@ -0,0 +1,334 @@
+use crate::log::{log_info, log_warn};
+use crate::{content_index, history, routing, settings};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedMove {
+    pub from: String,
+    pub to: String,
+}
+
+// Uma reorganização aplicada, guardada para poder ser desfeita
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalBatch {
+    pub id: String,
+    pub created_at: String,
+    pub moves: Vec<PlannedMove>,
+}
+
+fn journal_file_path() -> PathBuf {
+    let app_data_dir = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
+    app_data_dir
+        .join("UploadIASD")
+        .join("organize-journal.json")
+}
+
+pub fn load_journal() -> Vec<JournalBatch> {
+    fs::read_to_string(journal_file_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_journal(journal: &[JournalBatch]) -> Result<(), String> {
+    let journal_path = journal_file_path();
+    if let Some(parent) = journal_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Erro ao criar diretório: {}", e))?;
+    }
+
+    let json_str = serde_json::to_string_pretty(journal)
+        .map_err(|e| format!("Erro ao serializar diário de organização: {}", e))?;
+
+    // Gravar em arquivo temporário e renomear, para não corromper o diário
+    let temp_path = journal_path.with_extension("json.tmp");
+    fs::write(&temp_path, json_str)
+        .and_then(|_| fs::rename(&temp_path, &journal_path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&temp_path);
+            format!("Erro ao salvar diário de organização: {}", e)
+        })
+}
+
+// Estrutura organizada: base/AAAA-MM-DD/tipo/origem
+pub fn organized_dir(
+    base_dir: &Path,
+    file_name: &str,
+    activity_type: &str,
+    date: NaiveDate,
+) -> PathBuf {
+    base_dir
+        .join(date.format("%Y-%m-%d").to_string())
+        .join(routing::classify_file_type(Path::new(file_name)))
+        .join(activity_type)
+}
+
+// Pasta de um arquivo que está chegando: organizada por data/tipo quando a opção está
+// ativa e nenhuma regra de destino escolheu outra pasta
+pub fn arrival_dir(
+    base_dir: &Path,
+    routed_dir: PathBuf,
+    file_name: &str,
+    activity_type: &str,
+) -> Result<PathBuf, String> {
+    if routed_dir != base_dir || !settings::current().auto_organize {
+        return Ok(routed_dir);
+    }
+
+    let dir = organized_dir(
+        base_dir,
+        file_name,
+        activity_type,
+        Local::now().date_naive(),
+    );
+    fs::create_dir_all(&dir).map_err(|e| format!("Erro ao criar diretório organizado: {}", e))?;
+    Ok(dir)
+}
+
+// "nome (N).ext" sem colidir com arquivos existentes nem com destinos já planejados
+fn free_target(path: &Path, planned: &HashSet<PathBuf>) -> PathBuf {
+    if !path.exists() && !planned.contains(path) {
+        return path.to_path_buf();
+    }
+
+    let parent = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("arquivo");
+    let extension = path.extension().and_then(|s| s.to_str());
+
+    (1..)
+        .map(|counter| match extension {
+            Some(ext) => parent.join(format!("{} ({}).{}", stem, counter, ext)),
+            None => parent.join(format!("{} ({})", stem, counter)),
+        })
+        .find(|candidate| !candidate.exists() && !planned.contains(candidate))
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+// Data do arquivo: a do histórico, se houver, senão a de modificação
+fn file_date(path: &Path, entry: Option<&Value>) -> NaiveDate {
+    entry
+        .and_then(|e| e["date"].as_str())
+        .and_then(|d| NaiveDateTime::parse_from_str(d, "%Y-%m-%d %H:%M:%S").ok())
+        .map(|dt| dt.date())
+        .or_else(|| {
+            fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .map(|t| DateTime::<Local>::from(t).date_naive())
+        })
+        .unwrap_or_else(|| Local::now().date_naive())
+}
+
+// Planejar a organização dos arquivos soltos na raiz das pastas; subpastas não são tocadas
+pub fn plan(folders: &[(PathBuf, &str)]) -> Vec<PlannedMove> {
+    let mut planned: HashSet<PathBuf> = HashSet::new();
+    let mut moves = Vec::new();
+
+    for (base_dir, default_type) in folders {
+        let entries = match fs::read_dir(base_dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log_warn(&format!(
+                    "Não foi possível ler {}: {}",
+                    base_dir.display(),
+                    e
+                ));
+                continue;
+            }
+        };
+
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .filter(|p| {
+                let name = p.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                !name.starts_with('.') && !name.contains(".temp.")
+            })
+            .collect();
+        files.sort();
+
+        for path in files {
+            let file_name = match path.file_name().and_then(|n| n.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let path_str = path.to_string_lossy().to_string();
+            let entry = history::find_by_path(&path_str).ok().flatten();
+            let activity_type = entry
+                .as_ref()
+                .and_then(|e| e["type"].as_str())
+                .unwrap_or(default_type);
+
+            let target_dir = organized_dir(
+                base_dir,
+                &file_name,
+                activity_type,
+                file_date(&path, entry.as_ref()),
+            );
+            let target = free_target(&target_dir.join(&file_name), &planned);
+            planned.insert(target.clone());
+
+            moves.push(PlannedMove {
+                from: path_str,
+                to: target.to_string_lossy().to_string(),
+            });
+        }
+    }
+
+    moves
+}
+
+// Executar os movimentos e registrá-los no diário; devolve o lote aplicado e os erros
+pub fn apply(moves: &[PlannedMove]) -> Result<(Option<JournalBatch>, Vec<String>), String> {
+    let mut done = Vec::new();
+    let mut errors = Vec::new();
+
+    for planned in moves {
+        let (from, to) = (Path::new(&planned.from), Path::new(&planned.to));
+        if to.exists() {
+            errors.push(format!("Destino já existe: {}", planned.to));
+            continue;
+        }
+
+        let result = to
+            .parent()
+            .map(fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| fs::rename(from, to));
+        match result {
+            Ok(()) => done.push(planned.clone()),
+            Err(e) => errors.push(format!("Erro ao mover {}: {}", planned.from, e)),
+        }
+    }
+
+    if done.is_empty() {
+        return Ok((None, errors));
+    }
+
+    sync_paths(&done, false);
+
+    let batch = JournalBatch {
+        id: uuid::Uuid::new_v4().to_string(),
+        created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        moves: done,
+    };
+    let mut journal = load_journal();
+    journal.push(batch.clone());
+    save_journal(&journal)?;
+
+    log_info(&format!(
+        "Reorganização {} aplicada: {} arquivo(s) movido(s)",
+        batch.id,
+        batch.moves.len()
+    ));
+    Ok((Some(batch), errors))
+}
+
+// Desfazer uma reorganização (a mais recente, se nenhuma for indicada)
+pub fn undo(batch_id: Option<&str>) -> Result<(JournalBatch, usize, Vec<String>), String> {
+    let mut journal = load_journal();
+    let index = match batch_id {
+        Some(id) => journal
+            .iter()
+            .position(|b| b.id == id)
+            .ok_or_else(|| format!("Reorganização não encontrada: {}", id))?,
+        None => journal
+            .len()
+            .checked_sub(1)
+            .ok_or("Nenhuma reorganização para desfazer")?,
+    };
+    let batch = journal[index].clone();
+
+    let mut restored = Vec::new();
+    let mut failed = Vec::new();
+    let mut errors = Vec::new();
+
+    for planned in batch.moves.iter().rev() {
+        let (from, to) = (Path::new(&planned.from), Path::new(&planned.to));
+        if !to.exists() {
+            errors.push(format!("Arquivo não está mais em {}", planned.to));
+            failed.push(planned.clone());
+            continue;
+        }
+        if from.exists() {
+            errors.push(format!("Já existe um arquivo em {}", planned.from));
+            failed.push(planned.clone());
+            continue;
+        }
+
+        let result = from
+            .parent()
+            .map(fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| fs::rename(to, from));
+        match result {
+            Ok(()) => {
+                remove_empty_parents(to, 3);
+                restored.push(planned.clone());
+            }
+            Err(e) => {
+                errors.push(format!("Erro ao restaurar {}: {}", planned.from, e));
+                failed.push(planned.clone());
+            }
+        }
+    }
+
+    sync_paths(&restored, true);
+
+    // O lote só sai do diário quando tudo foi restaurado; o que falhou pode ser tentado de novo
+    if failed.is_empty() {
+        journal.remove(index);
+    } else {
+        failed.reverse();
+        journal[index].moves = failed;
+    }
+    save_journal(&journal)?;
+
+    log_info(&format!(
+        "Reorganização {} desfeita: {} arquivo(s) restaurado(s)",
+        batch.id,
+        restored.len()
+    ));
+    Ok((batch, restored.len(), errors))
+}
+
+// Pastas data/tipo/origem que ficaram vazias após desfazer
+fn remove_empty_parents(path: &Path, levels: usize) {
+    let mut current = path.parent();
+    for _ in 0..levels {
+        match current {
+            Some(dir) if fs::remove_dir(dir).is_ok() => current = dir.parent(),
+            _ => break,
+        }
+    }
+}
+
+// Atualizar caminhos no histórico e no índice de conteúdo
+fn sync_paths(moves: &[PlannedMove], reverse: bool) {
+    let pairs: Vec<(PathBuf, PathBuf)> = moves
+        .iter()
+        .map(|m| {
+            let (from, to) = (PathBuf::from(&m.from), PathBuf::from(&m.to));
+            if reverse {
+                (to, from)
+            } else {
+                (from, to)
+            }
+        })
+        .collect();
+
+    if let Err(e) = history::update_paths(&pairs) {
+        log_warn(&format!("Não foi possível atualizar o histórico: {}", e));
+    }
+    content_index::update_paths(&pairs);
+}
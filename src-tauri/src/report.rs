@@ -0,0 +1,409 @@
+use crate::filenames;
+use crate::folders;
+use crate::history::{self, HistoryQuery};
+use crate::log::log_info;
+use crate::stats::{self, Bucket, Window};
+use chrono::Local;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CSV_COLUMNS: [&str; 11] = [
+    "id",
+    "date",
+    "type",
+    "source",
+    "file_name",
+    "file_path",
+    "file_size",
+    "metadata",
+    "sender",
+    "chat_name",
+    "mime_type",
+];
+
+// Formatos de exportação do histórico
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            other => Err(format!("Formato inválido: {}. Use csv ou json.", other)),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+// Arquivo novo na pasta escolhida, sem sobrescrever exportações anteriores
+fn output_path(folder: &Path, stem: &str, extension: &str) -> Result<PathBuf, String> {
+    folders::check_folder(folder, false)?;
+    Ok(filenames::next_free_path(
+        &folder.join(format!("{}.{}", stem, extension)),
+    ))
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// Planilhas interpretam células iniciadas por = + - @ como fórmula; o apóstrofo força texto
+fn csv_cell(value: &Value) -> String {
+    let cell = text(value);
+    if cell.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", cell)
+    } else {
+        cell
+    }
+}
+
+fn write_csv(path: &Path, rows: &[Value]) -> Result<(), String> {
+    let mut writer =
+        csv::Writer::from_path(path).map_err(|e| format!("Erro ao criar CSV: {}", e))?;
+
+    writer
+        .write_record(CSV_COLUMNS)
+        .map_err(|e| format!("Erro ao gravar CSV: {}", e))?;
+    for row in rows {
+        writer
+            .write_record(CSV_COLUMNS.iter().map(|column| csv_cell(&row[*column])))
+            .map_err(|e| format!("Erro ao gravar CSV: {}", e))?;
+    }
+
+    writer
+        .flush()
+        .map_err(|e| format!("Erro ao gravar CSV: {}", e))
+}
+
+// Exportar o histórico filtrado; devolve o arquivo criado e a quantidade de atividades
+pub fn export_history(
+    folder: &Path,
+    format: ExportFormat,
+    query: &HistoryQuery,
+) -> Result<(PathBuf, usize), String> {
+    let rows = history::matching(query)?;
+    let stem = format!("historico-{}", Local::now().format("%Y%m%d-%H%M%S"));
+    let path = output_path(folder, &stem, format.extension())?;
+
+    match format {
+        ExportFormat::Csv => write_csv(&path, &rows)?,
+        ExportFormat::Json => {
+            let json_str = serde_json::to_string_pretty(&rows)
+                .map_err(|e| format!("Erro ao serializar histórico: {}", e))?;
+            fs::write(&path, json_str).map_err(|e| format!("Erro ao salvar exportação: {}", e))?;
+        }
+    }
+
+    log_info(&format!(
+        "Histórico exportado ({} atividade(s)) para {}",
+        rows.len(),
+        path.display()
+    ));
+    Ok((path, rows.len()))
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn format_bytes(bytes: i64) -> String {
+    let units = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes.max(0) as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, units[0])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
+
+fn type_label(activity_type: &str) -> &str {
+    match activity_type {
+        "upload" => "Upload",
+        "youtube_download" => "YouTube",
+        "whatsapp_receive" => "WhatsApp",
+        "livestream_capture" => "Transmissão",
+        other => other,
+    }
+}
+
+// Gráfico de colunas (série temporal)
+fn column_chart(data: &[(String, i64)]) -> String {
+    if data.is_empty() {
+        return "<p class=\"empty\">Sem atividades no período.</p>".to_string();
+    }
+
+    let (width, height, padding) = (720.0, 240.0, 30.0);
+    let max = data.iter().map(|(_, v)| *v).max().unwrap_or(1).max(1) as f64;
+    let slot = (width - padding * 2.0) / data.len() as f64;
+    let bar = (slot * 0.7).max(1.0);
+    let label_every = (data.len() / 12).max(1);
+
+    let mut svg = format!(
+        "<svg viewBox=\"0 0 {w} {h}\" width=\"100%\" role=\"img\">\
+         <line x1=\"{p}\" y1=\"{b}\" x2=\"{r}\" y2=\"{b}\" stroke=\"#999\"/>",
+        w = width,
+        h = height,
+        p = padding,
+        r = width - padding,
+        b = height - padding
+    );
+    for (i, (label, value)) in data.iter().enumerate() {
+        let bar_height = (*value as f64 / max) * (height - padding * 2.0);
+        let x = padding + slot * i as f64 + (slot - bar) / 2.0;
+        let y = height - padding - bar_height;
+        svg.push_str(&format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"#2563eb\">\
+             <title>{}: {}</title></rect>",
+            x,
+            y,
+            bar,
+            bar_height,
+            escape(label),
+            value
+        ));
+        if i % label_every == 0 {
+            svg.push_str(&format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"10\" text-anchor=\"middle\">{}</text>",
+                x + bar / 2.0,
+                height - padding + 14.0,
+                escape(label)
+            ));
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+// Gráfico de barras horizontais (distribuição)
+fn bar_chart(data: &[(String, i64)]) -> String {
+    if data.is_empty() {
+        return "<p class=\"empty\">Sem dados.</p>".to_string();
+    }
+
+    let (width, row, label_width) = (720.0, 26.0, 160.0);
+    let height = row * data.len() as f64 + 10.0;
+    let max = data.iter().map(|(_, v)| *v).max().unwrap_or(1).max(1) as f64;
+
+    let mut svg = format!(
+        "<svg viewBox=\"0 0 {} {}\" width=\"100%\" role=\"img\">",
+        width, height
+    );
+    for (i, (label, value)) in data.iter().enumerate() {
+        let y = 5.0 + row * i as f64;
+        let bar_width = (*value as f64 / max) * (width - label_width - 60.0);
+        svg.push_str(&format!(
+            "<text x=\"0\" y=\"{:.1}\" font-size=\"12\">{}</text>\
+             <rect x=\"{}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"#16a34a\"/>\
+             <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"12\">{}</text>",
+            y + 15.0,
+            escape(label),
+            label_width,
+            y + 3.0,
+            bar_width,
+            row - 8.0,
+            label_width + bar_width + 6.0,
+            y + 15.0,
+            value
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn pairs(items: &Value, key: &str, label: impl Fn(&str) -> String) -> Vec<(String, i64)> {
+    items
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| {
+                    (
+                        label(item[key].as_str().unwrap_or("")),
+                        item["count"].as_i64().unwrap_or(0),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn change(value: &Value) -> String {
+    match value.as_f64() {
+        Some(pct) if pct >= 0.0 => format!("+{:.1}%", pct),
+        Some(pct) => format!("{:.1}%", pct),
+        None => "—".to_string(),
+    }
+}
+
+fn render_report(statistics: &Value, files: &[Value]) -> String {
+    let period = &statistics["period"];
+    let period_label = match (period["from"].as_str(), period["to"].as_str()) {
+        (Some(from), Some(to)) => format!("{} a {}", from, to),
+        _ => "Todo o histórico".to_string(),
+    };
+    let comparison = &statistics["comparison"];
+
+    let series: Vec<(String, i64)> = pairs(&statistics["buckets"], "bucket", str::to_string);
+    let types = pairs(&statistics["types"], "type", |t| type_label(t).to_string());
+    let file_types = pairs(&statistics["top_file_types"], "file_type", str::to_string);
+
+    let senders: String = statistics["top_senders"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .map(|s| {
+                    format!(
+                        "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                        escape(s["sender"].as_str().unwrap_or("")),
+                        s["count"].as_i64().unwrap_or(0),
+                        format_bytes(s["bytes"].as_i64().unwrap_or(0))
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let file_rows: String = files
+        .iter()
+        .map(|f| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(f["date"].as_str().unwrap_or("")),
+                escape(type_label(f["type"].as_str().unwrap_or(""))),
+                escape(f["file_name"].as_str().unwrap_or("")),
+                format_bytes(f["file_size"].as_i64().unwrap_or(0)),
+                escape(f["metadata"].as_str().unwrap_or(""))
+            )
+        })
+        .collect();
+
+    let comparison_html = if comparison.is_null() {
+        String::new()
+    } else {
+        format!(
+            "<p>Período anterior ({} a {}): {} atividade(s), {}. Variação: {} atividades, {} volume.</p>",
+            escape(comparison["from"].as_str().unwrap_or("")),
+            escape(comparison["to"].as_str().unwrap_or("")),
+            comparison["total_activities"].as_i64().unwrap_or(0),
+            format_bytes(comparison["total_size"].as_i64().unwrap_or(0)),
+            change(&comparison["activities_change_pct"]),
+            change(&comparison["size_change_pct"])
+        )
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="pt-BR">
+<head>
+<meta charset="UTF-8">
+<title>Relatório de mídia - {period}</title>
+<style>
+body {{ font-family: Arial, sans-serif; margin: 32px; color: #222; }}
+h1 {{ margin-bottom: 4px; }}
+.muted, .empty {{ color: #666; }}
+.totals {{ display: flex; gap: 24px; margin: 16px 0; }}
+.totals div {{ border: 1px solid #ddd; border-radius: 8px; padding: 12px 20px; }}
+.totals strong {{ display: block; font-size: 24px; }}
+table {{ border-collapse: collapse; width: 100%; font-size: 13px; }}
+th, td {{ border-bottom: 1px solid #eee; padding: 6px; text-align: left; }}
+@media print {{ body {{ margin: 0; }} }}
+</style>
+</head>
+<body>
+<h1>Relatório de mídia</h1>
+<p class="muted">{period} · gerado em {generated}</p>
+<div class="totals">
+<div><strong>{total}</strong>atividades</div>
+<div><strong>{size}</strong>volume</div>
+</div>
+{comparison}
+<h2>Atividades por {bucket}</h2>
+{series}
+<h2>Por origem</h2>
+{types}
+<h2>Tipos de arquivo</h2>
+{file_types}
+<h2>Principais remetentes</h2>
+<table><tr><th>Remetente</th><th>Arquivos</th><th>Volume</th></tr>{senders}</table>
+<h2>Arquivos ({file_count})</h2>
+<table><tr><th>Data</th><th>Origem</th><th>Arquivo</th><th>Tamanho</th><th>Detalhes</th></tr>{file_rows}</table>
+</body>
+</html>
+"#,
+        period = escape(&period_label),
+        generated = Local::now().format("%Y-%m-%d %H:%M"),
+        total = statistics["total_activities"].as_i64().unwrap_or(0),
+        size = format_bytes(statistics["total_size"].as_i64().unwrap_or(0)),
+        comparison = comparison_html,
+        bucket = match period["bucket"].as_str() {
+            Some("week") => "semana",
+            Some("month") => "mês",
+            _ => "dia",
+        },
+        series = column_chart(&series),
+        types = bar_chart(&types),
+        file_types = bar_chart(&file_types),
+        senders = senders,
+        file_count = files.len(),
+        file_rows = file_rows
+    )
+}
+
+// Relatório HTML (imprimível como PDF) com totais, gráficos SVG e lista de arquivos
+pub fn generate_report(
+    folder: &Path,
+    window: Option<Window>,
+    bucket: Option<Bucket>,
+) -> Result<PathBuf, String> {
+    let statistics = stats::compute(window, bucket)?;
+    let files = history::matching(&HistoryQuery {
+        date_from: window.map(|w| w.from.format("%Y-%m-%d").to_string()),
+        date_to: window.map(|w| w.to.format("%Y-%m-%d").to_string()),
+        order: history::SortOrder::Asc,
+        ..HistoryQuery::default()
+    })?;
+
+    let stem = match window {
+        Some(w) => format!(
+            "relatorio-{}_a_{}",
+            w.from.format("%Y-%m-%d"),
+            w.to.format("%Y-%m-%d")
+        ),
+        None => format!("relatorio-{}", Local::now().format("%Y-%m-%d")),
+    };
+    let path = output_path(folder, &stem, "html")?;
+    fs::write(&path, render_report(&statistics, &files))
+        .map_err(|e| format!("Erro ao salvar relatório: {}", e))?;
+
+    log_info(&format!(
+        "Relatório gerado ({} arquivo(s)) em {}",
+        files.len(),
+        path.display()
+    ));
+    Ok(path)
+}
@@ -0,0 +1,511 @@
+use crate::folders::{self, FolderKind};
+use crate::log::{log_error, log_info, log_warn};
+use crate::routing::{self, FileSource};
+use crate::{content_index, history, settings};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+pub const DEFAULT_INTERVAL_HOURS: u32 = 24;
+pub const DEFAULT_ARCHIVE_FOLDER: &str = "Arquivo";
+
+// Impede execuções simultâneas (agendada e manual)
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RetentionAction {
+    #[default]
+    Delete,
+    Archive,
+}
+
+impl RetentionAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            RetentionAction::Delete => "delete",
+            RetentionAction::Archive => "archive",
+        }
+    }
+}
+
+// Regra de retenção: arquivos que combinam e são mais antigos que o limite são apagados ou arquivados
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetentionRule {
+    pub name: String,
+    pub enabled: bool,
+    // "uploads" ou "videos"; vazio = ambas
+    pub folder: String,
+    pub file_types: Vec<String>,
+    pub sources: Vec<FileSource>,
+    pub older_than_days: u32,
+    pub action: RetentionAction,
+    // Pasta de arquivamento, relativa à pasta de origem ou absoluta
+    pub archive_path: String,
+}
+
+impl Default for RetentionRule {
+    fn default() -> Self {
+        RetentionRule {
+            name: String::new(),
+            enabled: true,
+            folder: String::new(),
+            file_types: Vec::new(),
+            sources: Vec::new(),
+            older_than_days: 90,
+            action: RetentionAction::Delete,
+            archive_path: DEFAULT_ARCHIVE_FOLDER.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetentionSettings {
+    // Avaliadas em ordem; a primeira que combinar decide o destino do arquivo
+    pub rules: Vec<RetentionRule>,
+    // Espaço livre mínimo em GB; abaixo disso as regras de exclusão removem os mais antigos
+    pub min_free_gb: f64,
+    // Intervalo entre execuções automáticas; 0 = apenas ao iniciar ou manualmente
+    pub interval_hours: u32,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        RetentionSettings {
+            rules: Vec::new(),
+            min_free_gb: 0.0,
+            interval_hours: DEFAULT_INTERVAL_HOURS,
+        }
+    }
+}
+
+impl RetentionRule {
+    pub fn validate(&mut self) -> Result<(), String> {
+        self.name = self.name.trim().to_string();
+        let label = if self.name.is_empty() {
+            "sem nome".to_string()
+        } else {
+            self.name.clone()
+        };
+
+        self.folder = self.folder.trim().to_lowercase();
+        if !self.folder.is_empty() {
+            FolderKind::parse(&self.folder).map_err(|e| format!("Retenção {}: {}", label, e))?;
+        }
+
+        self.file_types = self
+            .file_types
+            .iter()
+            .map(|t| t.trim().to_lowercase())
+            .collect();
+        if let Some(invalid) = self
+            .file_types
+            .iter()
+            .find(|t| !routing::FILE_TYPES.contains(&t.as_str()))
+        {
+            return Err(format!(
+                "Retenção {}: tipo de arquivo inválido {}. Use {}",
+                label,
+                invalid,
+                routing::FILE_TYPES.join(", ")
+            ));
+        }
+
+        if self.older_than_days == 0 {
+            return Err(format!(
+                "Retenção {}: informe a idade mínima em dias (maior que zero)",
+                label
+            ));
+        }
+
+        self.archive_path = self.archive_path.trim().to_string();
+        if self.action == RetentionAction::Archive && self.archive_path.is_empty() {
+            return Err(format!(
+                "Retenção {}: informe a pasta de arquivamento",
+                label
+            ));
+        }
+        if Path::new(&self.archive_path)
+            .components()
+            .any(|c| matches!(c, std::path::Component::ParentDir))
+        {
+            return Err(format!(
+                "Retenção {}: a pasta de arquivamento não pode conter \"..\"",
+                label
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn applies_to(&self, kind: FolderKind) -> bool {
+        self.folder.is_empty() || self.folder == kind.as_str()
+    }
+
+    fn matches(&self, kind: FolderKind, file_type: &str, source: FileSource) -> bool {
+        self.enabled
+            && self.applies_to(kind)
+            && (self.file_types.is_empty() || self.file_types.iter().any(|t| t == file_type))
+            && (self.sources.is_empty() || self.sources.contains(&source))
+    }
+
+    fn archive_dir(&self, base_dir: &Path) -> PathBuf {
+        let path = Path::new(&self.archive_path);
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            base_dir.join(path)
+        }
+    }
+}
+
+impl RetentionSettings {
+    // Regras inválidas são descartadas e o motivo devolvido
+    pub fn validate(&mut self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        let rules = std::mem::take(&mut self.rules);
+        for mut rule in rules {
+            match rule.validate() {
+                Ok(()) => self.rules.push(rule),
+                Err(e) => problems.push(e),
+            }
+        }
+
+        if !self.min_free_gb.is_finite() || self.min_free_gb < 0.0 {
+            problems.push("Espaço livre mínimo deve ser zero ou positivo".to_string());
+            self.min_free_gb = 0.0;
+        }
+        if self.interval_hours > 24 * 30 {
+            problems.push("Intervalo da retenção deve ser de no máximo 720 horas".to_string());
+            self.interval_hours = DEFAULT_INTERVAL_HOURS;
+        }
+
+        problems
+    }
+}
+
+// Ação planejada para um arquivo
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedAction {
+    pub rule: String,
+    pub action: RetentionAction,
+    // "age" = passou da idade; "free_space" = abaixo do espaço livre mínimo
+    pub reason: &'static str,
+    pub path: String,
+    pub destination: Option<String>,
+    pub size: u64,
+    pub age_days: i64,
+}
+
+struct Candidate {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+    age_days: i64,
+    rule: usize,
+}
+
+// Arquivos da pasta, inclusive subpastas, exceto ocultos, temporários e pastas de arquivamento
+fn collect_files(dir: &Path, skip: &[PathBuf], out: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log_warn(&format!("Não foi possível ler {}: {}", dir.display(), e));
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') || name.contains(".temp.") || skip.contains(&path) {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, skip, out);
+        } else if path.is_file() {
+            out.push(path);
+        }
+    }
+}
+
+// Origem pelo histórico; sem registro, pela pasta em que o arquivo está
+fn file_source(path: &Path, kind: FolderKind) -> FileSource {
+    history::find_by_path(&path.to_string_lossy())
+        .ok()
+        .flatten()
+        .and_then(|entry| entry["type"].as_str().map(str::to_string))
+        .and_then(|activity_type| FileSource::parse(&activity_type).ok())
+        .unwrap_or(match kind {
+            FolderKind::Uploads => FileSource::Upload,
+            FolderKind::Videos => FileSource::Youtube,
+        })
+}
+
+fn plan_folder(
+    kind: FolderKind,
+    base_dir: &Path,
+    config: &RetentionSettings,
+    actions: &mut Vec<PlannedAction>,
+) {
+    let skip: Vec<PathBuf> = config
+        .rules
+        .iter()
+        .filter(|rule| rule.action == RetentionAction::Archive && rule.applies_to(kind))
+        .map(|rule| rule.archive_dir(base_dir))
+        .collect();
+    let mut files = Vec::new();
+    collect_files(base_dir, &skip, &mut files);
+
+    let now = SystemTime::now();
+    let mut waiting: Vec<Candidate> = Vec::new();
+    let mut freed: u64 = 0;
+
+    for path in files {
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        let modified = metadata.modified().unwrap_or(now);
+        let age_days = now
+            .duration_since(modified)
+            .map(|d| (d.as_secs() / 86_400) as i64)
+            .unwrap_or(0);
+        let file_type = routing::classify_file_type(&path);
+        let source = file_source(&path, kind);
+
+        let Some(index) = config
+            .rules
+            .iter()
+            .position(|rule| rule.matches(kind, file_type, source))
+        else {
+            continue;
+        };
+        let rule = &config.rules[index];
+
+        if age_days >= rule.older_than_days as i64 {
+            if rule.action == RetentionAction::Delete {
+                freed += metadata.len();
+            }
+            actions.push(planned(
+                rule,
+                base_dir,
+                &path,
+                metadata.len(),
+                age_days,
+                "age",
+            ));
+        } else {
+            waiting.push(Candidate {
+                path,
+                size: metadata.len(),
+                modified,
+                age_days,
+                rule: index,
+            });
+        }
+    }
+
+    if config.min_free_gb <= 0.0 {
+        return;
+    }
+
+    // Abaixo do mínimo: apagar os mais antigos entre os que têm regra de exclusão
+    let target = (config.min_free_gb * 1024.0 * 1024.0 * 1024.0) as u64;
+    let mut free = match fs2::available_space(base_dir) {
+        Ok(free) => free + freed,
+        Err(e) => {
+            log_warn(&format!(
+                "Erro ao consultar espaço livre em {}: {}",
+                base_dir.display(),
+                e
+            ));
+            return;
+        }
+    };
+
+    waiting.retain(|c| config.rules[c.rule].action == RetentionAction::Delete);
+    waiting.sort_by_key(|c| c.modified);
+    for candidate in waiting {
+        if free >= target {
+            break;
+        }
+        free += candidate.size;
+        actions.push(planned(
+            &config.rules[candidate.rule],
+            base_dir,
+            &candidate.path,
+            candidate.size,
+            candidate.age_days,
+            "free_space",
+        ));
+    }
+}
+
+fn planned(
+    rule: &RetentionRule,
+    base_dir: &Path,
+    path: &Path,
+    size: u64,
+    age_days: i64,
+    reason: &'static str,
+) -> PlannedAction {
+    // Arquivamento preserva a estrutura de subpastas
+    let destination = (rule.action == RetentionAction::Archive).then(|| {
+        let relative = path.strip_prefix(base_dir).unwrap_or(path);
+        let target = rule.archive_dir(base_dir).join(relative);
+        crate::filenames::next_free_path(&target)
+            .to_string_lossy()
+            .to_string()
+    });
+
+    PlannedAction {
+        rule: rule.name.clone(),
+        action: rule.action,
+        reason,
+        path: path.to_string_lossy().to_string(),
+        destination,
+        size,
+        age_days,
+    }
+}
+
+// Planejar as ações de todas as pastas disponíveis
+pub fn plan() -> Vec<PlannedAction> {
+    let config = settings::current().retention;
+    let mut actions = Vec::new();
+    if config.rules.iter().all(|rule| !rule.enabled) {
+        return actions;
+    }
+
+    for kind in FolderKind::ALL {
+        match folders::resolve(kind) {
+            Ok(base_dir) => plan_folder(kind, &base_dir, &config, &mut actions),
+            Err(e) => log_warn(&format!("Retenção ignorada: {}", e)),
+        }
+    }
+
+    actions
+}
+
+// Mover entre unidades diferentes exige copiar e apagar
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
+fn execute(action: &PlannedAction) -> Result<(), String> {
+    let path = Path::new(&action.path);
+    match (action.action, &action.destination) {
+        (RetentionAction::Delete, _) => {
+            fs::remove_file(path).map_err(|e| format!("Erro ao apagar {}: {}", action.path, e))
+        }
+        (RetentionAction::Archive, Some(destination)) => {
+            move_file(path, Path::new(destination))
+                .map_err(|e| format!("Erro ao arquivar {}: {}", action.path, e))?;
+            let moves = vec![(path.to_path_buf(), PathBuf::from(destination))];
+            if let Err(e) = history::update_paths(&moves) {
+                log_warn(&format!("Não foi possível atualizar o histórico: {}", e));
+            }
+            content_index::update_paths(&moves);
+            Ok(())
+        }
+        (RetentionAction::Archive, None) => Err(format!(
+            "Destino de arquivamento ausente para {}",
+            action.path
+        )),
+    }
+}
+
+// Executar as ações planejadas, registrando cada uma na auditoria do histórico e nos logs
+pub fn run() -> Result<(Vec<PlannedAction>, Vec<String>), String> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("Retenção já está em execução".to_string());
+    }
+
+    let mut done = Vec::new();
+    let mut errors = Vec::new();
+
+    for action in plan() {
+        match execute(&action) {
+            Ok(()) => {
+                log_info(&format!(
+                    "Retenção \"{}\": {} {} ({} dias)",
+                    action.rule,
+                    if action.action == RetentionAction::Delete {
+                        "apagado"
+                    } else {
+                        "arquivado"
+                    },
+                    action.path,
+                    action.age_days
+                ));
+                history::record_retention(
+                    action.action.as_str(),
+                    &action.rule,
+                    action.reason,
+                    &action.path,
+                    action.destination.as_deref(),
+                    action.size,
+                    action.age_days,
+                );
+                done.push(action);
+            }
+            Err(e) => {
+                log_error(&e);
+                errors.push(e);
+            }
+        }
+    }
+
+    RUNNING.store(false, Ordering::SeqCst);
+    Ok((done, errors))
+}
+
+fn run_logged(trigger: &str) {
+    match run() {
+        Ok((done, errors)) if !done.is_empty() || !errors.is_empty() => log_info(&format!(
+            "Retenção ({}): {} ação(ões), {} erro(s)",
+            trigger,
+            done.len(),
+            errors.len()
+        )),
+        Ok(_) => {}
+        Err(e) => log_warn(&e),
+    }
+}
+
+// Executar ao iniciar e depois no intervalo configurado
+pub fn start() {
+    std::thread::spawn(|| {
+        run_logged("inicialização");
+        let mut last_run = Local::now();
+
+        loop {
+            std::thread::sleep(Duration::from_secs(60));
+            let interval = settings::current().retention.interval_hours;
+            if interval == 0 {
+                continue;
+            }
+
+            let due: DateTime<Local> = last_run + chrono::Duration::hours(interval as i64);
+            if Local::now() >= due {
+                run_logged("agendada");
+                last_run = Local::now();
+            }
+        }
+    });
+}
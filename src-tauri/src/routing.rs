@@ -0,0 +1,346 @@
+use crate::filenames;
+use crate::log::log_info;
+use crate::settings;
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+pub const FILE_TYPES: [&str; 5] = ["audio", "video", "image", "document", "other"];
+
+pub const DESTINATION_PLACEHOLDERS: [&str; 8] = [
+    "year", "month", "day", "date", "type", "source", "sender", "ext",
+];
+
+// Origem de um arquivo recebido
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileSource {
+    Upload,
+    Whatsapp,
+    Youtube,
+}
+
+impl FileSource {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "upload" => Ok(FileSource::Upload),
+            "whatsapp" | "whatsapp_receive" => Ok(FileSource::Whatsapp),
+            "youtube" | "youtube_download" => Ok(FileSource::Youtube),
+            other => Err(format!(
+                "Origem desconhecida: {}. Use upload, whatsapp ou youtube.",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FileSource::Upload => "upload",
+            FileSource::Whatsapp => "whatsapp",
+            FileSource::Youtube => "youtube",
+        }
+    }
+}
+
+// Tipo do arquivo pela extensão (audio, video, image, document ou other)
+pub fn classify_file_type(path: &Path) -> &'static str {
+    let ext_lower = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => ext.to_lowercase(),
+        None => return "other",
+    };
+
+    if ["mp3", "wav", "ogg", "flac", "aac", "m4a"].contains(&ext_lower.as_str()) {
+        "audio"
+    } else if ["mp4", "avi", "mkv", "mov", "webm", "flv"].contains(&ext_lower.as_str()) {
+        "video"
+    } else if ["jpg", "jpeg", "png", "gif", "bmp", "webp"].contains(&ext_lower.as_str()) {
+        "image"
+    } else if ["pdf", "doc", "docx", "txt"].contains(&ext_lower.as_str()) {
+        "document"
+    } else {
+        "other"
+    }
+}
+
+// Regra de destino: todas as condições preenchidas precisam bater; vazias aceitam tudo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RoutingRule {
+    pub name: String,
+    pub enabled: bool,
+    pub sources: Vec<FileSource>,
+    pub file_types: Vec<String>,
+    pub extensions: Vec<String>,
+    // Trecho do nome ou número do remetente (WhatsApp) ou do canal (YouTube)
+    pub senders: Vec<String>,
+    // 0 = domingo ... 6 = sábado
+    pub weekdays: Vec<u32>,
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    // Pasta relativa à pasta da origem (ou absoluta), ex.: "Fotos/{year}-{month}"
+    pub destination: String,
+}
+
+impl Default for RoutingRule {
+    fn default() -> Self {
+        RoutingRule {
+            name: String::new(),
+            enabled: true,
+            sources: Vec::new(),
+            file_types: Vec::new(),
+            extensions: Vec::new(),
+            senders: Vec::new(),
+            weekdays: Vec::new(),
+            date_from: None,
+            date_to: None,
+            destination: String::new(),
+        }
+    }
+}
+
+// Arquivo que está chegando e precisa de uma pasta
+pub struct Incoming<'a> {
+    pub source: FileSource,
+    pub file_name: &'a str,
+    // Nome exibido e identificador (número) do remetente, quando houver
+    pub sender: Option<&'a str>,
+    pub sender_id: Option<&'a str>,
+    pub received_at: DateTime<Local>,
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("Data inválida: {}. Use AAAA-MM-DD.", value))
+}
+
+impl RoutingRule {
+    // Normalizar e validar a regra
+    pub fn validate(&mut self) -> Result<(), String> {
+        self.name = self.name.trim().to_string();
+        let label = if self.name.is_empty() {
+            "sem nome".to_string()
+        } else {
+            self.name.clone()
+        };
+
+        self.destination = self.destination.trim().to_string();
+        if self.destination.is_empty() {
+            return Err(format!("Regra {}: informe a pasta de destino", label));
+        }
+
+        let mut rest = self.destination.as_str();
+        while let Some(open) = rest.find('{') {
+            let after = &rest[open + 1..];
+            let close = after
+                .find('}')
+                .ok_or_else(|| format!("Regra {}: '{{' sem fechamento no destino", label))?;
+            let key = &after[..close];
+            if !DESTINATION_PLACEHOLDERS.contains(&key) {
+                return Err(format!(
+                    "Regra {}: marcador desconhecido {{{}}}. Disponíveis: {}",
+                    label,
+                    key,
+                    DESTINATION_PLACEHOLDERS.join(", ")
+                ));
+            }
+            rest = &after[close + 1..];
+        }
+
+        self.file_types = self
+            .file_types
+            .iter()
+            .map(|t| t.trim().to_lowercase())
+            .collect();
+        if let Some(invalid) = self
+            .file_types
+            .iter()
+            .find(|t| !FILE_TYPES.contains(&t.as_str()))
+        {
+            return Err(format!(
+                "Regra {}: tipo de arquivo inválido {}. Use {}",
+                label,
+                invalid,
+                FILE_TYPES.join(", ")
+            ));
+        }
+
+        self.extensions = self
+            .extensions
+            .iter()
+            .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+            .filter(|e| !e.is_empty())
+            .collect();
+        self.senders = self
+            .senders
+            .iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        if self.weekdays.iter().any(|d| *d > 6) {
+            return Err(format!(
+                "Regra {}: dias da semana vão de 0 (domingo) a 6 (sábado)",
+                label
+            ));
+        }
+
+        for date in [&self.date_from, &self.date_to].into_iter().flatten() {
+            parse_date(date).map_err(|e| format!("Regra {}: {}", label, e))?;
+        }
+
+        Ok(())
+    }
+
+    fn matches(&self, incoming: &Incoming, file_type: &str) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if !self.sources.is_empty() && !self.sources.contains(&incoming.source) {
+            return false;
+        }
+        if !self.file_types.is_empty() && !self.file_types.iter().any(|t| t == file_type) {
+            return false;
+        }
+
+        if !self.extensions.is_empty() {
+            let ext = Path::new(incoming.file_name)
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("")
+                .to_lowercase();
+            if !self.extensions.contains(&ext) {
+                return false;
+            }
+        }
+
+        if !self.senders.is_empty() {
+            let candidates: Vec<String> = [incoming.sender, incoming.sender_id]
+                .into_iter()
+                .flatten()
+                .map(str::to_lowercase)
+                .collect();
+            let matched = self.senders.iter().any(|wanted| {
+                let wanted = wanted.to_lowercase();
+                candidates.iter().any(|c| c.contains(&wanted))
+            });
+            if !matched {
+                return false;
+            }
+        }
+
+        let date = incoming.received_at.date_naive();
+        if !self.weekdays.is_empty()
+            && !self
+                .weekdays
+                .contains(&date.weekday().num_days_from_sunday())
+        {
+            return false;
+        }
+        if let Some(from) = self.date_from.as_deref().and_then(|d| parse_date(d).ok()) {
+            if date < from {
+                return false;
+            }
+        }
+        if let Some(to) = self.date_to.as_deref().and_then(|d| parse_date(d).ok()) {
+            if date > to {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // Montar a pasta de destino; ".." é descartado para não sair da pasta base
+    fn render_destination(&self, incoming: &Incoming, file_type: &str, base_dir: &Path) -> PathBuf {
+        let at = incoming.received_at;
+        let ext = Path::new(incoming.file_name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        let values = [
+            ("year", at.format("%Y").to_string()),
+            ("month", at.format("%m").to_string()),
+            ("day", at.format("%d").to_string()),
+            ("date", at.format("%Y-%m-%d").to_string()),
+            ("type", file_type.to_string()),
+            ("source", incoming.source.as_str().to_string()),
+            (
+                "sender",
+                incoming
+                    .sender
+                    .or(incoming.sender_id)
+                    .unwrap_or("desconhecido")
+                    .to_string(),
+            ),
+            ("ext", ext),
+        ];
+
+        let mut rendered = self.destination.clone();
+        for (key, value) in &values {
+            rendered = rendered.replace(&format!("{{{}}}", key), value);
+        }
+
+        let rendered_path = PathBuf::from(rendered.replace('\\', "/"));
+        let mut destination = if rendered_path.is_absolute() {
+            PathBuf::new()
+        } else {
+            base_dir.to_path_buf()
+        };
+
+        for component in rendered_path.components() {
+            match component {
+                Component::Prefix(_) | Component::RootDir => destination.push(component),
+                Component::Normal(part) => {
+                    destination.push(filenames::sanitize_stem(&part.to_string_lossy()))
+                }
+                Component::CurDir | Component::ParentDir => {}
+            }
+        }
+
+        destination
+    }
+}
+
+// Primeira regra que combina com o arquivo e a pasta resultante (pasta base se nenhuma)
+pub fn evaluate<'r>(
+    rules: &'r [RoutingRule],
+    incoming: &Incoming,
+    base_dir: &Path,
+) -> (PathBuf, Option<&'r RoutingRule>) {
+    let file_type = classify_file_type(Path::new(incoming.file_name));
+
+    match rules.iter().find(|rule| rule.matches(incoming, file_type)) {
+        Some(rule) => (
+            rule.render_destination(incoming, file_type, base_dir),
+            Some(rule),
+        ),
+        None => (base_dir.to_path_buf(), None),
+    }
+}
+
+// Aplicar as regras configuradas e garantir que a pasta exista
+pub fn route(incoming: &Incoming, base_dir: &Path) -> Result<PathBuf, String> {
+    let rules = settings::current().routing_rules;
+    let (destination, rule) = evaluate(&rules, incoming, base_dir);
+
+    if let Some(rule) = rule {
+        log_info(&format!(
+            "Regra de destino \"{}\": {} -> {}",
+            rule.name,
+            incoming.file_name,
+            destination.display()
+        ));
+    }
+
+    fs::create_dir_all(&destination).map_err(|e| {
+        format!(
+            "Erro ao criar pasta de destino {}: {}",
+            destination.display(),
+            e
+        )
+    })?;
+
+    Ok(destination)
+}
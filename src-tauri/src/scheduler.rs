@@ -0,0 +1,539 @@
+use crate::download_error::{self, DownloadError};
+use crate::filenames;
+use crate::log::{log_error, log_info, log_warn};
+use chrono::{Datelike, Local, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+const TIME_FORMAT: &str = "%H:%M";
+
+// Intervalo entre verificações das regras
+const TICK: Duration = Duration::from_secs(30);
+// Uma regra ainda dispara se o app abrir até 15 minutos após o horário
+const GRACE_MINUTES: i64 = 15;
+// Pausa entre tentativas enquanto a transmissão não começa
+const LIVE_RETRY_PAUSE: Duration = Duration::from_secs(60);
+
+static SCHEDULER_STARTED: Mutex<bool> = Mutex::new(false);
+static RUNNING_RULES: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+static RULES_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ScheduleKind {
+    // Execução única em "AAAA-MM-DDTHH:MM"
+    Once { at: String },
+    // Toda semana; weekday 0 = domingo ... 6 = sábado
+    Weekly { weekday: u32, time: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleRule {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    // Canal (https://www.youtube.com/@igreja) ou link direto da transmissão
+    pub channel_url: String,
+    pub kind: ScheduleKind,
+    // Quanto tempo esperar a transmissão começar
+    #[serde(default = "default_wait_minutes")]
+    pub wait_minutes: u32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub last_triggered: Option<String>,
+    #[serde(default)]
+    pub last_status: Option<String>,
+}
+
+fn default_wait_minutes() -> u32 {
+    120
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn schedules_file_path() -> PathBuf {
+    let app_data_dir = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
+    app_data_dir.join("UploadIASD").join("schedules.json")
+}
+
+fn load_rules() -> Vec<ScheduleRule> {
+    fs::read_to_string(schedules_file_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_rules(rules: &[ScheduleRule]) -> Result<(), String> {
+    let path = schedules_file_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Erro ao criar diretório: {}", e))?;
+    }
+
+    let json_str = serde_json::to_string_pretty(rules)
+        .map_err(|e| format!("Erro ao serializar agendamentos: {}", e))?;
+    fs::write(&path, json_str).map_err(|e| format!("Erro ao salvar agendamentos: {}", e))
+}
+
+fn update_rule<F>(id: &str, change: F)
+where
+    F: FnOnce(&mut ScheduleRule),
+{
+    let _guard = match RULES_LOCK.lock() {
+        Ok(guard) => guard,
+        Err(_) => return,
+    };
+    let mut rules = load_rules();
+    if let Some(rule) = rules.iter_mut().find(|r| r.id == id) {
+        change(rule);
+        if let Err(e) = save_rules(&rules) {
+            log_warn(&format!(
+                "Não foi possível atualizar agendamento {}: {}",
+                id, e
+            ));
+        }
+    }
+}
+
+fn validate_rule(rule: &ScheduleRule) -> Result<(), String> {
+    if rule.name.trim().is_empty() {
+        return Err("Informe um nome para o agendamento".to_string());
+    }
+
+    let url = rule.channel_url.trim();
+    if !(url.starts_with("https://www.youtube.com/")
+        || url.starts_with("https://youtube.com/")
+        || url.starts_with("https://youtu.be/"))
+    {
+        return Err("Informe o link do canal ou da transmissão no YouTube".to_string());
+    }
+
+    match &rule.kind {
+        ScheduleKind::Once { at } => {
+            NaiveDateTime::parse_from_str(at, DATETIME_FORMAT)
+                .map_err(|_| format!("Data/hora inválida: {} (use AAAA-MM-DDTHH:MM)", at))?;
+        }
+        ScheduleKind::Weekly { weekday, time } => {
+            if *weekday > 6 {
+                return Err("Dia da semana deve ser de 0 (domingo) a 6 (sábado)".to_string());
+            }
+            NaiveTime::parse_from_str(time, TIME_FORMAT)
+                .map_err(|_| format!("Horário inválido: {} (use HH:MM)", time))?;
+        }
+    }
+
+    if rule.wait_minutes == 0 || rule.wait_minutes > 24 * 60 {
+        return Err("Tempo de espera deve estar entre 1 minuto e 24 horas".to_string());
+    }
+
+    Ok(())
+}
+
+pub fn list_rules() -> Vec<ScheduleRule> {
+    load_rules()
+}
+
+// Criar ou atualizar regra (id vazio cria uma nova)
+pub fn save_rule(mut rule: ScheduleRule) -> Result<ScheduleRule, String> {
+    validate_rule(&rule)?;
+
+    let _guard = RULES_LOCK
+        .lock()
+        .map_err(|e| format!("Erro ao acessar agendamentos: {}", e))?;
+    let mut rules = load_rules();
+
+    rule.channel_url = rule.channel_url.trim().to_string();
+    if rule.id.is_empty() {
+        rule.id = uuid::Uuid::new_v4().to_string();
+        rules.push(rule.clone());
+    } else if let Some(existing) = rules.iter_mut().find(|r| r.id == rule.id) {
+        // Estado de execução é mantido pelo agendador, não pelo formulário
+        rule.last_triggered = existing.last_triggered.clone();
+        rule.last_status = existing.last_status.clone();
+        *existing = rule.clone();
+    } else {
+        return Err(format!("Agendamento não encontrado: {}", rule.id));
+    }
+
+    save_rules(&rules)?;
+    log_info(&format!("Agendamento salvo: {} ({})", rule.name, rule.id));
+    Ok(rule)
+}
+
+pub fn delete_rule(id: &str) -> Result<(), String> {
+    let _guard = RULES_LOCK
+        .lock()
+        .map_err(|e| format!("Erro ao acessar agendamentos: {}", e))?;
+    let mut rules = load_rules();
+    let before = rules.len();
+    rules.retain(|r| r.id != id);
+
+    if rules.len() == before {
+        return Err(format!("Agendamento não encontrado: {}", id));
+    }
+
+    save_rules(&rules)?;
+    log_info(&format!("Agendamento removido: {}", id));
+    Ok(())
+}
+
+// Situação de uma regra no momento da verificação
+enum Due {
+    // Disparar agora (horário no formato DATETIME_FORMAT)
+    Run(String),
+    // Execução única cujo horário passou há mais que a tolerância (app estava fechado)
+    Missed(String),
+}
+
+fn due_occurrence(rule: &ScheduleRule, now: NaiveDateTime) -> Option<Due> {
+    let grace = chrono::Duration::minutes(GRACE_MINUTES);
+    let (occurrence, stale) = match &rule.kind {
+        ScheduleKind::Once { at } => {
+            let at = NaiveDateTime::parse_from_str(at, DATETIME_FORMAT).ok()?;
+            if at > now {
+                return None;
+            }
+            (at, now - at > grace)
+        }
+        ScheduleKind::Weekly { weekday, time } => {
+            let time = NaiveTime::parse_from_str(time, TIME_FORMAT).ok()?;
+            let today = now.date();
+            let days_back = (today.weekday().num_days_from_sunday() + 7 - weekday) % 7;
+            let mut occurrence = (today - chrono::Duration::days(days_back as i64)).and_time(time);
+            if occurrence > now {
+                occurrence -= chrono::Duration::days(7);
+            }
+            // Semanal atrasada: simplesmente espera a próxima semana
+            if now - occurrence > grace {
+                return None;
+            }
+            (occurrence, false)
+        }
+    };
+
+    let key = occurrence.format(DATETIME_FORMAT).to_string();
+    if rule.last_triggered.as_deref() == Some(key.as_str()) {
+        None
+    } else if stale {
+        Some(Due::Missed(key))
+    } else {
+        Some(Due::Run(key))
+    }
+}
+
+fn live_url(channel_url: &str) -> String {
+    let url = channel_url.trim().trim_end_matches('/');
+    if url.contains("watch?v=") || url.contains("/live/") || url.ends_with("/live") {
+        url.to_string()
+    } else {
+        format!("{}/live", url)
+    }
+}
+
+fn mark_running(id: &str) -> bool {
+    match RUNNING_RULES.lock() {
+        Ok(mut running) => running
+            .get_or_insert_with(HashSet::new)
+            .insert(id.to_string()),
+        Err(_) => false,
+    }
+}
+
+fn mark_finished(id: &str) {
+    if let Ok(mut running) = RUNNING_RULES.lock() {
+        if let Some(set) = running.as_mut() {
+            set.remove(id);
+        }
+    }
+}
+
+// Ler a saída em paralelo para o processo não travar com o buffer cheio
+fn read_pipe<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+// Executar o yt-dlp, encerrando-o se o prazo passar antes de a gravação começar
+// (None = encerrado no prazo); uma gravação em andamento segue até o fim
+fn run_until_deadline(
+    mut command: Command,
+    deadline: Instant,
+    recording_started: &dyn Fn() -> bool,
+) -> Result<Option<Output>, String> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Erro ao executar yt-dlp: {}", e))?;
+
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|e| format!("Erro ao aguardar yt-dlp: {}", e))?
+        {
+            break status;
+        }
+        if Instant::now() >= deadline && !recording_started() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    };
+
+    Ok(Some(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    }))
+}
+
+// Aguardar a transmissão começar e gravá-la na pasta de vídeos
+fn capture_live(rule: &ScheduleRule) -> Result<PathBuf, String> {
+    let yt_dlp_path = crate::get_yt_dlp_path()?;
+    let output_dir = crate::get_videos_path()?;
+    fs::create_dir_all(&output_dir).map_err(|e| format!("Erro ao criar pasta: {}", e))?;
+
+    let stem = filenames::sanitize_stem(&format!(
+        "{}_{}",
+        Local::now().format("%Y-%m-%d_%H-%M"),
+        crate::sanitize_title(&rule.name)
+    ));
+    let output_template = output_dir.join(format!("{}.%(ext)s", stem));
+    let url = live_url(&rule.channel_url);
+    let deadline = Instant::now() + Duration::from_secs(rule.wait_minutes as u64 * 60);
+    let mut last_error = String::new();
+
+    log_info(&format!(
+        "Agendamento {}: aguardando transmissão em {} por até {} min",
+        rule.name, url, rule.wait_minutes
+    ));
+
+    // Arquivos da gravação (parciais inclusive) indicam que a transmissão começou
+    let recording_started = || {
+        let prefix = format!("{}.", stem);
+        fs::read_dir(&output_dir).is_ok_and(|entries| {
+            entries
+                .flatten()
+                .any(|e| e.file_name().to_string_lossy().starts_with(&prefix))
+        })
+    };
+
+    while Instant::now() < deadline {
+        let mut command = crate::yt_dlp_command(&yt_dlp_path);
+        command.args([
+            "--live-from-start",
+            "--wait-for-video",
+            "30-300",
+            "-f",
+            "bestvideo+bestaudio/best",
+            "--merge-output-format",
+            "mp4",
+            "--no-warnings",
+            "--no-mtime",
+            "--print",
+            "after_move:filepath",
+            "-o",
+            &output_template.to_string_lossy(),
+            &url,
+        ]);
+
+        let output = match run_until_deadline(command, deadline, &recording_started)? {
+            Some(output) => output,
+            None => {
+                log_warn(&format!(
+                    "Agendamento {}: prazo de espera esgotado; yt-dlp encerrado",
+                    rule.name
+                ));
+                break;
+            }
+        };
+
+        if output.status.success() {
+            let printed = String::from_utf8_lossy(&output.stdout);
+            let path = printed
+                .lines()
+                .map(str::trim)
+                .rfind(|line| !line.is_empty())
+                .map(PathBuf::from)
+                .filter(|path| path.exists())
+                .ok_or("Gravação concluída, mas o arquivo final não foi encontrado")?;
+            return Ok(path);
+        }
+
+        last_error = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let error = DownloadError::classify(&last_error);
+
+        // Login, disco cheio, FFmpeg ausente...: esperar mais não resolve
+        if !matches!(
+            error,
+            DownloadError::LiveNotStarted
+                | DownloadError::Network
+                | DownloadError::Unavailable
+                | DownloadError::Unknown
+        ) {
+            return Err(format!(
+                "{} [{}] {}",
+                error.message(crate::get_cookies_file_path().exists()),
+                error.code(),
+                download_error::error_detail(&last_error)
+            ));
+        }
+
+        // Canal ainda não está ao vivo: tentar de novo até o prazo
+        log_info(&format!(
+            "Agendamento {}: transmissão indisponível, nova tentativa em {}s ({})",
+            rule.name,
+            LIVE_RETRY_PAUSE.as_secs(),
+            last_error.lines().last().unwrap_or("")
+        ));
+        std::thread::sleep(LIVE_RETRY_PAUSE);
+    }
+
+    Err(format!(
+        "Transmissão não começou em {} min. Último erro: {}",
+        rule.wait_minutes, last_error
+    ))
+}
+
+fn run_rule(rule: ScheduleRule) {
+    if !mark_running(&rule.id) {
+        log_warn(&format!(
+            "Agendamento {} já está em execução; disparo ignorado",
+            rule.name
+        ));
+        return;
+    }
+
+    std::thread::spawn(move || {
+        update_rule(&rule.id, |r| r.last_status = Some("running".to_string()));
+
+        let result = capture_live(&rule);
+
+        let status = match &result {
+            Ok(path) => {
+                let file_size = path.metadata().map(|m| m.len()).unwrap_or(0);
+                log_info(&format!(
+                    "Agendamento {}: transmissão gravada em {}",
+                    rule.name,
+                    path.display()
+                ));
+                crate::history::record(
+                    "livestream_capture",
+                    &path.to_string_lossy(),
+                    file_size,
+                    Some(&rule.name),
+                    json!({
+                        "schedule_id": rule.id,
+                        "schedule_name": rule.name,
+                        "source_url": live_url(&rule.channel_url),
+                        "status": "success"
+                    }),
+                );
+                "success"
+            }
+            // Sem arquivo: caminho vazio e tamanho 0, fora da limpeza de ausentes e dos
+            // totais de bytes; o nome do agendamento identifica a linha
+            Err(err) => {
+                log_error(&format!("Agendamento {} falhou: {}", rule.name, err));
+                crate::history::record(
+                    "livestream_capture",
+                    "",
+                    0,
+                    Some(&rule.name),
+                    json!({
+                        "file_name": rule.name,
+                        "schedule_id": rule.id,
+                        "schedule_name": rule.name,
+                        "source_url": live_url(&rule.channel_url),
+                        "status": "failed",
+                        "error": err
+                    }),
+                );
+                "failed"
+            }
+        };
+
+        update_rule(&rule.id, |r| {
+            r.last_status = Some(status.to_string());
+            if matches!(r.kind, ScheduleKind::Once { .. }) {
+                r.enabled = false;
+            }
+        });
+        mark_finished(&rule.id);
+    });
+}
+
+// Disparar manualmente uma regra, fora do horário
+pub fn run_now(id: &str) -> Result<(), String> {
+    let rule = load_rules()
+        .into_iter()
+        .find(|r| r.id == id)
+        .ok_or_else(|| format!("Agendamento não encontrado: {}", id))?;
+    run_rule(rule);
+    Ok(())
+}
+
+fn tick() {
+    let now = Local::now().naive_local();
+    for rule in load_rules().into_iter().filter(|r| r.enabled) {
+        match due_occurrence(&rule, now) {
+            Some(Due::Run(occurrence)) => {
+                log_info(&format!(
+                    "Agendamento {} disparado ({})",
+                    rule.name, occurrence
+                ));
+                update_rule(&rule.id, |r| r.last_triggered = Some(occurrence.clone()));
+                run_rule(rule);
+            }
+            Some(Due::Missed(occurrence)) => {
+                log_warn(&format!(
+                    "Agendamento {} perdido: horário {} passou com o app fechado",
+                    rule.name, occurrence
+                ));
+                update_rule(&rule.id, |r| {
+                    r.last_triggered = Some(occurrence.clone());
+                    r.last_status = Some("missed".to_string());
+                    r.enabled = false;
+                });
+            }
+            None => {}
+        }
+    }
+}
+
+// Iniciar verificação periódica dos agendamentos (uma vez por execução do app)
+pub fn start() {
+    match SCHEDULER_STARTED.lock() {
+        Ok(mut started) if !*started => *started = true,
+        _ => return,
+    }
+
+    std::thread::spawn(|| {
+        log_info("Agendador de gravações iniciado");
+        loop {
+            tick();
+            std::thread::sleep(TICK);
+        }
+    });
+}
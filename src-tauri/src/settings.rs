@@ -0,0 +1,551 @@
+use crate::backup::BackupSettings;
+use crate::filenames::{self, NamingSettings};
+use crate::folders;
+use crate::log::{log_info, log_warn, LoggingSettings};
+use crate::network::{self, NetworkSettings};
+use crate::retention::RetentionSettings;
+use crate::routing::RoutingRule;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+
+// Versão atual do esquema do config.json
+pub const SETTINGS_VERSION: u32 = 1;
+
+// Configurações compartilhadas entre comandos, servidor de upload e agendador
+pub type SharedSettings = Arc<RwLock<Settings>>;
+
+static SETTINGS: OnceLock<SharedSettings> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub version: u32,
+    // Vazio = pasta padrão
+    pub uploads_path: String,
+    pub videos_path: String,
+    pub naming: NamingSettings,
+    pub duplicate_detection: bool,
+    pub network: NetworkSettings,
+    // Avaliadas em ordem; a primeira que combinar define a pasta
+    pub routing_rules: Vec<RoutingRule>,
+    // Organizar arquivos recebidos em data/tipo/origem
+    pub auto_organize: bool,
+    pub retention: RetentionSettings,
+    pub backup: BackupSettings,
+    pub logging: LoggingSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            version: SETTINGS_VERSION,
+            uploads_path: String::new(),
+            videos_path: String::new(),
+            naming: NamingSettings::default(),
+            duplicate_detection: true,
+            network: NetworkSettings::default(),
+            routing_rules: Vec::new(),
+            auto_organize: false,
+            retention: RetentionSettings::default(),
+            backup: BackupSettings::default(),
+            logging: LoggingSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    // Normalizar valores; campos inválidos voltam ao padrão e o motivo é devolvido
+    fn normalize(&mut self) -> Vec<String> {
+        let defaults = Settings::default();
+        let mut problems = Vec::new();
+
+        self.version = SETTINGS_VERSION;
+
+        for (path, label) in [
+            (&mut self.uploads_path, "uploads"),
+            (&mut self.videos_path, "vídeos"),
+        ] {
+            *path = path.trim().to_string();
+            if let Err(e) = validate_folder(path) {
+                problems.push(format!("Pasta de {}: {}", label, e));
+                path.clear();
+            }
+        }
+
+        let naming = &mut self.naming;
+        naming.download_template = naming.download_template.trim().to_string();
+        if let Err(e) = filenames::validate_template(
+            &naming.download_template,
+            &filenames::DOWNLOAD_PLACEHOLDERS,
+        ) {
+            problems.push(e);
+            naming.download_template = defaults.naming.download_template;
+        }
+        naming.upload_template = naming.upload_template.trim().to_string();
+        if let Err(e) =
+            filenames::validate_template(&naming.upload_template, &filenames::UPLOAD_PLACEHOLDERS)
+        {
+            problems.push(e);
+            naming.upload_template = defaults.naming.upload_template;
+        }
+
+        let net = &mut self.network;
+        match network::parse_rate_limit(net.rate_limit.as_deref().unwrap_or("")) {
+            Ok(rate) => net.rate_limit = rate,
+            Err(e) => {
+                problems.push(e);
+                net.rate_limit = None;
+            }
+        }
+        match network::parse_proxy(net.proxy.as_deref().unwrap_or("")) {
+            Ok(proxy) => net.proxy = proxy,
+            Err(e) => {
+                problems.push(e);
+                net.proxy = None;
+            }
+        }
+        // Interface pode estar desconectada agora; resolvida a cada chamada do yt-dlp
+        net.source_address = net
+            .source_address
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string);
+        if let Err(e) = network::validate_retries(net.retries) {
+            problems.push(e);
+            net.retries = defaults.network.retries;
+        }
+        if let Err(e) = network::validate_socket_timeout(net.socket_timeout) {
+            problems.push(e);
+            net.socket_timeout = defaults.network.socket_timeout;
+        }
+        match network::parse_player_client(&net.player_client) {
+            Ok(client) => net.player_client = client,
+            Err(e) => {
+                problems.push(e);
+                net.player_client = defaults.network.player_client;
+            }
+        }
+
+        let rules = std::mem::take(&mut self.routing_rules);
+        for mut rule in rules {
+            match rule.validate() {
+                Ok(()) => self.routing_rules.push(rule),
+                Err(e) => problems.push(e),
+            }
+        }
+
+        problems.extend(self.retention.validate());
+        problems.extend(self.backup.validate());
+        problems.extend(self.logging.validate());
+
+        problems
+    }
+}
+
+pub fn config_file_path() -> PathBuf {
+    let app_data_dir = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
+    app_data_dir.join("UploadIASD").join("config.json")
+}
+
+// Pasta configurada precisa ser absoluta e não pode ser um arquivo
+fn validate_folder(path: &str) -> Result<(), String> {
+    if path.is_empty() {
+        return Ok(());
+    }
+
+    let folder = Path::new(path);
+    if !folder.is_absolute() {
+        return Err(format!("caminho precisa ser absoluto: {}", path));
+    }
+    if folder.is_file() {
+        return Err(format!("o caminho aponta para um arquivo: {}", path));
+    }
+    Ok(())
+}
+
+// Locais onde versões antigas do plugin-store gravavam "settings.json"
+fn legacy_store_paths() -> Vec<PathBuf> {
+    let app_data_dir = match dirs::data_local_dir() {
+        Some(dir) => dir,
+        None => return Vec::new(),
+    };
+
+    vec![
+        app_data_dir
+            .join("com.gabrielkramer.uploadiasddesktop")
+            .join("store")
+            .join("settings.json"),
+        app_data_dir
+            .join("com.tauri.app")
+            .join("store")
+            .join("settings.json"),
+        app_data_dir.join("uploadiasddesktop").join("settings.json"),
+    ]
+}
+
+// Primeiro valor não vazio de uma chave nos arquivos antigos do plugin-store
+fn legacy_store_value(key: &str) -> Option<String> {
+    legacy_store_paths().iter().find_map(|path| {
+        let content = fs::read_to_string(path).ok()?;
+        let json = serde_json::from_str::<Value>(&content).ok()?;
+        let settings = json.get("settings").unwrap_or(&json);
+        settings
+            .get(key)
+            .and_then(|v| v.as_str())
+            .filter(|v| !v.trim().is_empty())
+            .map(str::to_string)
+    })
+}
+
+// v0 (sem "version"): config.json só com uploadsPath/videosPath, e pastas às vezes
+// gravadas apenas no plugin-store
+fn migrate_v0(config: &mut Map<String, Value>) {
+    for key in ["uploadsPath", "videosPath"] {
+        let missing = config
+            .get(key)
+            .and_then(|v| v.as_str())
+            .is_none_or(|v| v.trim().is_empty());
+        if missing {
+            if let Some(value) = legacy_store_value(key) {
+                log_info(&format!("Configuração {} migrada do plugin-store", key));
+                config.insert(key.to_string(), json!(value));
+            }
+        }
+    }
+
+    config.insert("version".to_string(), json!(1));
+}
+
+// Aplicar migrações em sequência até a versão atual
+fn migrate(config: &mut Map<String, Value>) -> bool {
+    let start = config.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+    let mut version = start;
+
+    while version < SETTINGS_VERSION as u64 {
+        match version {
+            0 => migrate_v0(config),
+            _ => break,
+        }
+        version = config
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(version + 1);
+    }
+
+    if version != start {
+        log_info(&format!(
+            "Configurações migradas da versão {} para {}",
+            start, version
+        ));
+    }
+    version != start
+}
+
+// Campo com tipo errado não deve descartar o restante: aproveitar o que for válido
+fn deserialize_lenient(config: Map<String, Value>) -> Settings {
+    let error = match serde_json::from_value(Value::Object(config.clone())) {
+        Ok(settings) => return settings,
+        Err(e) => e,
+    };
+    log_warn(&format!("config.json com valores inválidos: {}", error));
+
+    let mut value = serde_json::to_value(Settings::default()).unwrap_or_default();
+    for (key, field) in config {
+        let mut candidate = value.clone();
+        candidate[&key] = field;
+        if serde_json::from_value::<Settings>(candidate.clone()).is_ok() {
+            value = candidate;
+        } else {
+            log_warn(&format!("Configuração {} ignorada; usando padrão", key));
+        }
+    }
+
+    serde_json::from_value(value).unwrap_or_default()
+}
+
+// Ler config.json, migrar e validar; ausente ou ilegível = padrões
+fn load() -> Settings {
+    let config_path = config_file_path();
+    let mut config = fs::read_to_string(&config_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|value| match value {
+            Value::Object(map) => Some(map),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let migrated = migrate(&mut config);
+    let mut settings = deserialize_lenient(config);
+
+    for problem in settings.normalize() {
+        log_warn(&format!("Configuração ignorada: {}", problem));
+    }
+
+    if migrated {
+        if let Err(e) = save(&settings) {
+            log_warn(&format!(
+                "Não foi possível gravar configurações migradas: {}",
+                e
+            ));
+        }
+    }
+
+    settings
+}
+
+// Gravar em arquivo temporário e renomear, para nunca deixar um config.json pela metade
+fn save(settings: &Settings) -> Result<(), String> {
+    let config_path = config_file_path();
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Erro ao criar diretório de configuração: {}", e))?;
+    }
+
+    let json_str = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Erro ao serializar configuração: {}", e))?;
+
+    let temp_path = config_path.with_extension("json.tmp");
+    fs::write(&temp_path, json_str).map_err(|e| format!("Erro ao salvar configuração: {}", e))?;
+    fs::rename(&temp_path, &config_path).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        format!("Erro ao salvar configuração: {}", e)
+    })
+}
+
+// Estado compartilhado, carregado do disco na primeira utilização
+pub fn shared() -> SharedSettings {
+    SETTINGS
+        .get_or_init(|| Arc::new(RwLock::new(load())))
+        .clone()
+}
+
+pub fn current() -> Settings {
+    shared()
+        .read()
+        .map(|settings| settings.clone())
+        .unwrap_or_default()
+}
+
+// Mesclar objetos JSON recursivamente (campos ausentes no patch ficam como estão)
+fn merge(target: &mut Value, patch: &Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+// Aplicar alterações parciais, validar e gravar; nada é salvo se algum valor for inválido
+pub fn update(patch: &Value) -> Result<Settings, String> {
+    if !patch.is_object() {
+        return Err("Alterações de configuração devem ser um objeto JSON".to_string());
+    }
+
+    let shared = shared();
+    let mut guard = shared
+        .write()
+        .map_err(|e| format!("Erro ao acessar configurações: {}", e))?;
+
+    let mut value = serde_json::to_value(&*guard)
+        .map_err(|e| format!("Erro ao serializar configuração: {}", e))?;
+    merge(&mut value, patch);
+
+    let mut updated: Settings =
+        serde_json::from_value(value).map_err(|e| format!("Configuração inválida: {}", e))?;
+    let mut problems = updated.normalize();
+
+    // Pastas novas precisam existir (ou ser criáveis) e aceitar gravação
+    for (new_path, old_path) in [
+        (&updated.uploads_path, &guard.uploads_path),
+        (&updated.videos_path, &guard.videos_path),
+    ] {
+        if !new_path.is_empty() && new_path != old_path {
+            if let Err(e) = folders::check_folder(Path::new(new_path), true) {
+                problems.push(e);
+            }
+        }
+    }
+
+    // Ao salvar, a interface/IP de origem precisa existir nesta máquina
+    if let Some(source) = &updated.network.source_address {
+        if let Err(e) = network::parse_source_address(source) {
+            problems.push(e);
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(problems.join("; "));
+    }
+
+    save(&updated)?;
+    *guard = updated.clone();
+    Ok(updated)
+}
+
+// Salvar todas as configurações num arquivo JSON para levar a outro computador
+pub fn export(path: &Path) -> Result<(), String> {
+    let payload = json!({
+        "app": "UploadIASD",
+        "appVersion": env!("CARGO_PKG_VERSION"),
+        "exportedAt": chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        "settings": current()
+    });
+
+    let json_str = serde_json::to_string_pretty(&payload)
+        .map_err(|e| format!("Erro ao serializar configuração: {}", e))?;
+    fs::write(path, json_str).map_err(|e| {
+        format!(
+            "Erro ao exportar configurações para {}: {}",
+            path.display(),
+            e
+        )
+    })
+}
+
+// Parte fixa de um caminho absoluto, antes do primeiro marcador ({year}, {sender}...)
+fn fixed_folder(path: &str) -> Option<PathBuf> {
+    let path = Path::new(path.trim());
+    if !path.is_absolute() {
+        return None;
+    }
+    Some(
+        path.components()
+            .take_while(|c| !c.as_os_str().to_string_lossy().contains('{'))
+            .collect(),
+    )
+}
+
+// Pastas absolutas do destino de backup, do arquivamento e das regras de destino
+fn imported_folders(imported: &Value) -> Vec<(String, PathBuf)> {
+    let mut found = Vec::new();
+    let mut add = |field: String, value: &Value| {
+        if let Some(folder) = value.as_str().and_then(fixed_folder) {
+            found.push((field, folder));
+        }
+    };
+
+    add(
+        "backup.destination".to_string(),
+        &imported["backup"]["destination"],
+    );
+    if let Some(rules) = imported["retention"]["rules"].as_array() {
+        for (i, rule) in rules.iter().enumerate() {
+            add(
+                format!("retention.rules[{}].archivePath", i),
+                &rule["archivePath"],
+            );
+        }
+    }
+    if let Some(rules) = imported["routingRules"].as_array() {
+        for (i, rule) in rules.iter().enumerate() {
+            add(
+                format!("routingRules[{}].destination", i),
+                &rule["destination"],
+            );
+        }
+    }
+
+    found
+}
+
+// Importar e mesclar configurações; pastas de mídia e interfaces inexistentes nesta
+// máquina não são aplicadas, e toda pasta indisponível volta na lista de avisos
+pub fn import(path: &Path) -> Result<(Settings, Vec<Value>), String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Erro ao ler {}: {}", path.display(), e))?;
+    let value: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Arquivo de configurações inválido: {}", e))?;
+
+    let mut imported = match value.get("settings").cloned().unwrap_or(value) {
+        Value::Object(map) => map,
+        _ => return Err("Arquivo não contém configurações do UploadIASD".to_string()),
+    };
+
+    let version = imported
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    if version > SETTINGS_VERSION as u64 {
+        return Err(format!(
+            "Arquivo exportado por uma versão mais nova do aplicativo (esquema {})",
+            version
+        ));
+    }
+    migrate(&mut imported);
+    imported.remove("version");
+
+    let mut flagged = Vec::new();
+
+    for key in ["uploadsPath", "videosPath"] {
+        let folder = imported
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(|v| v.trim().to_string())
+            .unwrap_or_default();
+        if folder.is_empty() {
+            continue;
+        }
+        if let Err(e) = folders::check_folder(Path::new(&folder), false) {
+            flagged.push(json!({
+                "field": key,
+                "value": folder,
+                "reason": format!("{}; mantida a configuração atual", e)
+            }));
+            imported.remove(key);
+        }
+    }
+
+    // Demais pastas são aplicadas, mas avisadas se não estiverem disponíveis aqui
+    for (field, folder) in imported_folders(&Value::Object(imported.clone())) {
+        if let Err(e) = folders::check_folder(&folder, false) {
+            flagged.push(json!({
+                "field": field,
+                "value": folder.display().to_string(),
+                "reason": e
+            }));
+        }
+    }
+
+    if let Some(Value::Object(net)) = imported.get_mut("network") {
+        let source = net
+            .get("sourceAddress")
+            .and_then(|v| v.as_str())
+            .map(|v| v.trim().to_string())
+            .unwrap_or_default();
+        if !source.is_empty() {
+            if let Err(e) = network::parse_source_address(&source) {
+                flagged.push(json!({
+                    "field": "network.sourceAddress",
+                    "value": source,
+                    "reason": e
+                }));
+                net.remove("sourceAddress");
+            }
+        }
+    }
+
+    let settings = update(&Value::Object(imported))?;
+    Ok((settings, flagged))
+}
+
+// Voltar aos padrões (o store da interface é limpo em reset_settings)
+pub fn reset() -> Result<Settings, String> {
+    let shared = shared();
+    let mut guard = shared
+        .write()
+        .map_err(|e| format!("Erro ao acessar configurações: {}", e))?;
+
+    let defaults = Settings::default();
+    save(&defaults)?;
+    *guard = defaults.clone();
+
+    Ok(defaults)
+}
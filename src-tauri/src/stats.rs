@@ -0,0 +1,327 @@
+use crate::history::{row_to_json, with_db};
+use crate::routing;
+use chrono::{Duration, Local, NaiveDate};
+use rusqlite::{params, Connection};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::path::Path;
+
+const TOP_LIMIT: i64 = 10;
+
+// Agrupamento das séries temporais
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bucket {
+    Day,
+    Week,
+    Month,
+}
+
+impl Bucket {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "day" | "dia" => Ok(Bucket::Day),
+            "week" | "semana" => Ok(Bucket::Week),
+            "month" | "mes" | "mês" => Ok(Bucket::Month),
+            other => Err(format!(
+                "Agrupamento inválido: {}. Use day, week ou month.",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Bucket::Day => "day",
+            Bucket::Week => "week",
+            Bucket::Month => "month",
+        }
+    }
+
+    // Expressão SQL que dá o início do grupo (semanas começam na segunda-feira)
+    fn sql(self) -> &'static str {
+        match self {
+            Bucket::Day => "substr(date, 1, 10)",
+            Bucket::Week => "date(substr(date, 1, 10), '-6 days', 'weekday 1')",
+            Bucket::Month => "substr(date, 1, 7)",
+        }
+    }
+
+    // Padrão conforme o tamanho do período
+    fn for_days(days: i64) -> Self {
+        if days <= 31 {
+            Bucket::Day
+        } else if days <= 180 {
+            Bucket::Week
+        } else {
+            Bucket::Month
+        }
+    }
+}
+
+// Período consultado; None = todo o histórico
+#[derive(Debug, Clone, Copy)]
+pub struct Window {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl Window {
+    fn days(self) -> i64 {
+        (self.to - self.from).num_days() + 1
+    }
+
+    // Período de mesmo tamanho imediatamente anterior
+    fn previous(self) -> Window {
+        let to = self.from - Duration::days(1);
+        Window {
+            from: to - Duration::days(self.days() - 1),
+            to,
+        }
+    }
+
+    fn bounds(self) -> (String, String) {
+        (
+            self.from.format("%Y-%m-%d").to_string(),
+            format!("{} 23:59:59", self.to.format("%Y-%m-%d")),
+        )
+    }
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn parse_day(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("Data inválida: {}. Use AAAA-MM-DD.", value))
+}
+
+// "7d", "30d", "365d", "all" ou "custom" (com datas); sem período = todo o histórico
+pub fn parse_window(
+    period: Option<&str>,
+    date_from: Option<&str>,
+    date_to: Option<&str>,
+) -> Result<Option<Window>, String> {
+    let today = Local::now().date_naive();
+    let last_days = |days: i64| {
+        Some(Window {
+            from: today - Duration::days(days - 1),
+            to: today,
+        })
+    };
+
+    let period = non_empty(period).map(str::to_lowercase);
+    match period.as_deref() {
+        None if non_empty(date_from).is_none() && non_empty(date_to).is_none() => Ok(None),
+        Some("all") => Ok(None),
+        Some("7" | "7d" | "week") => Ok(last_days(7)),
+        Some("30" | "30d" | "month") => Ok(last_days(30)),
+        Some("365" | "365d" | "year") => Ok(last_days(365)),
+        None | Some("custom") => {
+            let from = non_empty(date_from).ok_or("Informe a data inicial do período")?;
+            let from = parse_day(from)?;
+            let to = match non_empty(date_to) {
+                Some(d) => parse_day(d)?,
+                None => today,
+            };
+            if from > to {
+                return Err("Data inicial posterior à data final".to_string());
+            }
+            Ok(Some(Window { from, to }))
+        }
+        Some(other) => Err(format!(
+            "Período inválido: {}. Use 7d, 30d, 365d, all ou custom.",
+            other
+        )),
+    }
+}
+
+// Agrupamento informado pela interface; vazio = padrão conforme o período
+pub fn parse_bucket(value: Option<&str>) -> Result<Option<Bucket>, String> {
+    non_empty(value).map(Bucket::parse).transpose()
+}
+
+fn percent_change(current: i64, previous: i64) -> Value {
+    if previous == 0 {
+        return Value::Null;
+    }
+    let change = (current - previous) as f64 / previous as f64 * 100.0;
+    json!((change * 10.0).round() / 10.0)
+}
+
+fn totals(conn: &Connection, from: &str, to: &str) -> rusqlite::Result<(i64, i64)> {
+    conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(file_size), 0) FROM activities
+         WHERE date >= ?1 AND date <= ?2",
+        params![from, to],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+}
+
+// Contagem e bytes agrupados por uma expressão SQL, em ordem decrescente de quantidade
+fn grouped(
+    conn: &Connection,
+    expression: &str,
+    from: &str,
+    to: &str,
+    limit: i64,
+) -> rusqlite::Result<Vec<(String, i64, i64)>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {expr} AS key, COUNT(*) AS count, COALESCE(SUM(file_size), 0) AS bytes
+         FROM activities
+         WHERE date >= ?1 AND date <= ?2 AND {expr} IS NOT NULL AND {expr} != ''
+         GROUP BY key ORDER BY count DESC, key LIMIT ?3",
+        expr = expression
+    ))?;
+    let rows = stmt.query_map(params![from, to, limit], |row| {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    })?;
+    rows.collect()
+}
+
+// Estatísticas do período: totais, séries por grupo, principais remetentes e tipos
+pub fn compute(window: Option<Window>, bucket: Option<Bucket>) -> Result<Value, String> {
+    let bucket = bucket.unwrap_or_else(|| match window {
+        Some(w) => Bucket::for_days(w.days()),
+        None => Bucket::Month,
+    });
+    let (from, to) = match window {
+        Some(w) => w.bounds(),
+        None => ("0000-00-00".to_string(), "9999-12-31 23:59:59".to_string()),
+    };
+    let previous = window.map(Window::previous);
+
+    with_db(|conn| {
+        let (total, total_size) = totals(conn, &from, &to)?;
+
+        let by_type_rows = grouped(conn, "type", &from, &to, i64::MAX)?;
+        let by_type: Map<String, Value> = by_type_rows
+            .iter()
+            .map(|(key, count, _)| (key.clone(), json!(count)))
+            .collect();
+        let types: Vec<Value> = by_type_rows
+            .iter()
+            .map(|(key, count, bytes)| json!({ "type": key, "count": count, "bytes": bytes }))
+            .collect();
+
+        let by_date: Map<String, Value> =
+            grouped(conn, "substr(date, 1, 10)", &from, &to, i64::MAX)?
+                .into_iter()
+                .map(|(key, count, _)| (key, json!(count)))
+                .collect();
+
+        let by_source: Vec<Value> = grouped(conn, "source", &from, &to, i64::MAX)?
+            .into_iter()
+            .map(|(key, count, bytes)| json!({ "source": key, "count": count, "bytes": bytes }))
+            .collect();
+
+        let top_senders: Vec<Value> = grouped(
+            conn,
+            "COALESCE(NULLIF(json_extract(details, '$.sender'), ''), json_extract(details, '$.sender_id'))",
+            &from,
+            &to,
+            TOP_LIMIT,
+        )?
+        .into_iter()
+        .map(|(key, count, bytes)| json!({ "sender": key, "count": count, "bytes": bytes }))
+        .collect();
+
+        // Séries: contagem e bytes por tipo de atividade em cada grupo
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} AS bucket, type, COUNT(*), COALESCE(SUM(file_size), 0)
+             FROM activities WHERE date >= ?1 AND date <= ?2
+             GROUP BY bucket, type ORDER BY bucket",
+            bucket.sql()
+        ))?;
+        let mut buckets: Vec<(String, Map<String, Value>, i64, i64)> = Vec::new();
+        let mut rows = stmt.query(params![from, to])?;
+        while let Some(row) = rows.next()? {
+            let (key, activity_type): (String, String) = (row.get(0)?, row.get(1)?);
+            let (count, bytes): (i64, i64) = (row.get(2)?, row.get(3)?);
+            if buckets.last().map(|b| &b.0) != Some(&key) {
+                buckets.push((key, Map::new(), 0, 0));
+            }
+            if let Some(entry) = buckets.last_mut() {
+                entry
+                    .1
+                    .insert(activity_type, json!({ "count": count, "bytes": bytes }));
+                entry.2 += count;
+                entry.3 += bytes;
+            }
+        }
+        let buckets: Vec<Value> = buckets
+            .into_iter()
+            .map(|(key, by_type, count, bytes)| {
+                json!({ "bucket": key, "count": count, "bytes": bytes, "by_type": by_type })
+            })
+            .collect();
+
+        // Tipos de arquivo pela extensão, como nas regras de destino
+        let mut file_types: HashMap<&'static str, (i64, i64)> = HashMap::new();
+        let mut stmt = conn.prepare(
+            "SELECT file_name, file_size FROM activities
+             WHERE date >= ?1 AND date <= ?2 AND file_path != ''",
+        )?;
+        let mut rows = stmt.query(params![from, to])?;
+        while let Some(row) = rows.next()? {
+            let (file_name, size): (String, i64) = (row.get(0)?, row.get(1)?);
+            let entry = file_types
+                .entry(routing::classify_file_type(Path::new(&file_name)))
+                .or_default();
+            entry.0 += 1;
+            entry.1 += size;
+        }
+        let mut top_file_types: Vec<(&str, i64, i64)> = file_types
+            .into_iter()
+            .map(|(key, (count, bytes))| (key, count, bytes))
+            .collect();
+        top_file_types.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        let top_file_types: Vec<Value> = top_file_types
+            .into_iter()
+            .map(|(key, count, bytes)| json!({ "file_type": key, "count": count, "bytes": bytes }))
+            .collect();
+
+        let mut stmt = conn.prepare(
+            "SELECT * FROM activities WHERE date >= ?1 AND date <= ?2
+             ORDER BY timestamp DESC, rowid DESC LIMIT 10",
+        )?;
+        let recent = stmt
+            .query_map(params![from, to], row_to_json)?
+            .collect::<rusqlite::Result<Vec<Value>>>()?;
+
+        let comparison = match previous {
+            Some(prev) => {
+                let (prev_from, prev_to) = prev.bounds();
+                let (prev_total, prev_size) = totals(conn, &prev_from, &prev_to)?;
+                json!({
+                    "from": prev.from.format("%Y-%m-%d").to_string(),
+                    "to": prev.to.format("%Y-%m-%d").to_string(),
+                    "total_activities": prev_total,
+                    "total_size": prev_size,
+                    "activities_change_pct": percent_change(total, prev_total),
+                    "size_change_pct": percent_change(total_size, prev_size)
+                })
+            }
+            None => Value::Null,
+        };
+
+        Ok(json!({
+            "period": {
+                "from": window.map(|w| w.from.format("%Y-%m-%d").to_string()),
+                "to": window.map(|w| w.to.format("%Y-%m-%d").to_string()),
+                "bucket": bucket.as_str()
+            },
+            "total_activities": total,
+            "total_size": total_size,
+            "by_type": by_type,
+            "by_date": by_date,
+            "types": types,
+            "by_source": by_source,
+            "buckets": buckets,
+            "top_senders": top_senders,
+            "top_file_types": top_file_types,
+            "comparison": comparison,
+            "recent_activities": recent
+        }))
+    })
+}
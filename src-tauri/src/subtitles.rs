@@ -0,0 +1,186 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Quantas linhas recentes são comparadas ao mesclar legendas repetidas
+// (legendas automáticas do YouTube repetem a linha anterior em cada trecho)
+const DUPLICATE_WINDOW: usize = 3;
+
+pub const SUBTITLE_EXTENSIONS: [&str; 2] = ["srt", "vtt"];
+
+// Resumir idiomas de legendas e legendas automáticas a partir do JSON do yt-dlp
+pub fn summarize_languages(info: &Value) -> Value {
+    let languages = |key: &str| -> Vec<String> {
+        let mut langs: Vec<String> = info
+            .get(key)
+            .and_then(|v| v.as_object())
+            .map(|map| map.keys().cloned().collect())
+            .unwrap_or_default();
+        langs.sort();
+        langs
+    };
+
+    json!({
+        "subtitles": languages("subtitles"),
+        "automatic_captions": languages("automatic_captions")
+    })
+}
+
+// Legendas geradas por este download (ex.: Titulo.pt.srt), pelo nome exato de saída;
+// idiomas com curinga ("all", "pt.*") aceitam qualquer idioma, se escritos após `since`
+pub fn find_subtitle_files(
+    dir: &Path,
+    stem: &str,
+    languages: &[&str],
+    format: &str,
+    since: SystemTime,
+) -> Vec<PathBuf> {
+    let is_pattern = |lang: &str| lang == "all" || lang.contains(['*', '.', '^', '$', '|']);
+    let mut files: Vec<PathBuf> = languages
+        .iter()
+        .filter(|lang| !is_pattern(lang))
+        .map(|lang| dir.join(format!("{}.{}.{}", stem, lang, format)))
+        .filter(|path| path.is_file())
+        .collect();
+
+    if languages.iter().any(|lang| is_pattern(lang)) {
+        let prefix = format!("{}.", stem);
+        let suffix = format!(".{}", format);
+        let matched = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|entry| {
+                        let name = entry.file_name().to_string_lossy().to_string();
+                        let lang = name
+                            .strip_prefix(&prefix)
+                            .and_then(|rest| rest.strip_suffix(&suffix))
+                            .unwrap_or("");
+                        !lang.is_empty()
+                            && !lang.contains('.')
+                            && entry
+                                .metadata()
+                                .and_then(|m| m.modified())
+                                .is_ok_and(|modified| modified >= since)
+                    })
+                    .map(|entry| entry.path())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        files.extend(matched);
+    }
+
+    files.sort();
+    files.dedup();
+    files
+}
+
+// Remover marcações de estilo (<c>, <i>, <00:00:01.000>) e posicionamento ({\an8})
+fn strip_markup(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut depth_angle = false;
+    let mut depth_brace = false;
+
+    for c in line.chars() {
+        match c {
+            '<' => depth_angle = true,
+            '>' if depth_angle => depth_angle = false,
+            '{' => depth_brace = true,
+            '}' if depth_brace => depth_brace = false,
+            _ if !depth_angle && !depth_brace => result.push(c),
+            _ => {}
+        }
+    }
+
+    result
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Gerar transcrição em texto puro a partir de uma legenda SRT ou VTT
+pub fn transcript_from_subtitles(content: &str) -> String {
+    let source: Vec<&str> = content
+        .lines()
+        .map(|l| l.trim().trim_start_matches('\u{feff}'))
+        .collect();
+    let mut lines: Vec<String> = Vec::new();
+    let mut skipping_block = false;
+    let mut block_start = true;
+
+    for (index, &line) in source.iter().enumerate() {
+        if line.is_empty() {
+            skipping_block = false;
+            block_start = true;
+            continue;
+        }
+        let at_block_start = block_start;
+        block_start = false;
+
+        // Cabeçalho e blocos de metadados do WebVTT
+        if line.starts_with("WEBVTT")
+            || line.starts_with("NOTE")
+            || line.starts_with("STYLE")
+            || line.starts_with("REGION")
+        {
+            skipping_block = true;
+            continue;
+        }
+        // Número/identificador do trecho: início do bloco, logo antes da linha de tempo
+        let is_cue_id = at_block_start
+            && source
+                .get(index + 1)
+                .is_some_and(|next| next.contains("-->"));
+        if skipping_block
+            || line.starts_with("Kind:")
+            || line.starts_with("Language:")
+            || is_cue_id
+            || line.contains("-->")
+        {
+            continue;
+        }
+
+        let text = strip_markup(line);
+        if text.is_empty() {
+            continue;
+        }
+
+        let start = lines.len().saturating_sub(DUPLICATE_WINDOW);
+        if lines[start..].iter().any(|previous| previous == &text) {
+            continue;
+        }
+
+        lines.push(text);
+    }
+
+    lines.join("\n")
+}
+
+// Exportar transcrição de um arquivo de legenda para .txt
+pub fn export_transcript(
+    subtitle_path: &Path,
+    output_path: Option<&Path>,
+) -> Result<PathBuf, String> {
+    let content = fs::read_to_string(subtitle_path)
+        .map_err(|e| format!("Erro ao ler legenda {}: {}", subtitle_path.display(), e))?;
+
+    let transcript = transcript_from_subtitles(&content);
+    if transcript.is_empty() {
+        return Err("A legenda não contém texto para transcrever".to_string());
+    }
+
+    let output = output_path
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| subtitle_path.with_extension("txt"));
+
+    fs::write(&output, transcript).map_err(|e| format!("Erro ao salvar transcrição: {}", e))?;
+
+    Ok(output)
+}
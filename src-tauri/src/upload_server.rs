@@ -1,4 +1,7 @@
+use crate::content_index;
+use crate::filenames::{self, Resolution};
 use crate::log::{log_error, log_info};
+use crate::{history, organize, routing, settings};
 use axum::{
     extract::{DefaultBodyLimit, Extension, Multipart},
     http::StatusCode,
@@ -7,13 +10,11 @@ use axum::{
     Json, Router,
 };
 use chrono::Local;
-use dirs;
 use serde_json::json;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tower_http::cors::CorsLayer;
-use uuid::Uuid;
 
 // Função auxiliar para formatar tamanho
 fn format_size(bytes: usize) -> String {
@@ -35,62 +36,6 @@ struct AppState {
     upload_dir: PathBuf,
 }
 
-// Função auxiliar para registrar atividade no histórico
-fn record_activity(activity_type: &str, file_path: &str, file_size: u64, metadata: Option<&str>) {
-    if let Some(app_data_dir) = dirs::data_local_dir() {
-        let history_path = app_data_dir.join("UploadIASD").join("history.json");
-
-        // Criar diretório se não existir
-        if let Some(parent) = history_path.parent() {
-            let _ = fs::create_dir_all(parent);
-        }
-
-        // Ler histórico existente
-        let mut history: Vec<serde_json::Value> = if history_path.exists() {
-            if let Ok(content) = fs::read_to_string(&history_path) {
-                serde_json::from_str(&content).unwrap_or_else(|_| vec![])
-            } else {
-                vec![]
-            }
-        } else {
-            vec![]
-        };
-
-        // Criar entrada de atividade
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        let activity = json!({
-            "id": format!("{}-{}", timestamp, Uuid::new_v4().to_string().chars().take(8).collect::<String>()),
-            "type": activity_type,
-            "file_path": file_path,
-            "file_name": PathBuf::from(file_path).file_name()
-                .and_then(|n| n.to_str())
-                .map(|s| s.to_string())
-                .unwrap_or_else(|| "arquivo".to_string()),
-            "file_size": file_size,
-            "metadata": metadata.unwrap_or(""),
-            "timestamp": timestamp,
-            "date": Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
-        });
-
-        // Adicionar no início (mais recente primeiro)
-        history.insert(0, activity);
-
-        // Manter apenas últimos 1000 registros
-        if history.len() > 1000 {
-            history.truncate(1000);
-        }
-
-        // Salvar histórico
-        if let Ok(json_str) = serde_json::to_string_pretty(&history) {
-            let _ = fs::write(&history_path, json_str);
-        }
-    }
-}
-
 // Página HTML para upload
 async fn upload_page() -> Html<&'static str> {
     Html(
@@ -415,6 +360,11 @@ async fn upload_files(
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
     let mut uploaded_count = 0;
     let mut errors = Vec::new();
+    let mut skipped: Vec<String> = Vec::new();
+    let mut duplicates: Vec<serde_json::Value> = Vec::new();
+    // Lidas a cada envio, para valerem sem reiniciar o servidor
+    let current_settings = settings::current();
+    let naming = current_settings.naming;
 
     // Processar campos multipart com timeout e tratamento robusto de erros
     loop {
@@ -476,38 +426,115 @@ async fn upload_files(
                         }
                     };
 
-                    // Sanitizar nome do arquivo (remover caracteres perigosos)
-                    let sanitized_filename = filename
-                        .chars()
-                        .map(|c| match c {
-                            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
-                            _ => c,
-                        })
-                        .collect::<String>();
-
-                    // Gerar nome único se arquivo já existir
-                    let mut file_path = state.upload_dir.join(&sanitized_filename);
-                    let mut counter = 1;
-
-                    // Se arquivo já existe, adicionar número antes da extensão
-                    while file_path.exists() {
-                        let stem = file_path
-                            .file_stem()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or("file");
-                        let extension =
-                            file_path.extension().and_then(|s| s.to_str()).unwrap_or("");
-
-                        let new_filename = if extension.is_empty() {
-                            format!("{} ({})", stem, counter)
+                    // Sanitizar nome do arquivo (caracteres perigosos, nomes reservados, tamanho)
+                    let sanitized_filename = filenames::sanitize_file_name(&filename);
+                    let original = std::path::Path::new(&sanitized_filename);
+                    let original_stem = original
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("arquivo");
+                    let extension = original.extension().and_then(|s| s.to_str()).unwrap_or("");
+
+                    // Conteúdo idêntico já recebido: não salvar outra cópia. Hash e consulta
+                    // ao índice (que pode reler arquivos) ficam fora do runtime assíncrono
+                    let check_duplicates = current_settings.duplicate_detection;
+                    let hashed = data.clone();
+                    let lookup = tokio::task::spawn_blocking(move || {
+                        let hash = content_index::hash_bytes(&hashed);
+                        let existing = if check_duplicates {
+                            content_index::find_hash(&hash)
                         } else {
-                            format!("{} ({}).{}", stem, counter, extension)
+                            None
                         };
-
-                        file_path = state.upload_dir.join(&new_filename);
-                        counter += 1;
+                        (hash, existing)
+                    })
+                    .await;
+                    let (content_hash, existing) = match lookup {
+                        Ok(result) => result,
+                        Err(e) => {
+                            let err_msg =
+                                format!("Erro ao verificar duplicidade de {}: {}", filename, e);
+                            errors.push(err_msg.clone());
+                            log_error(&format!("ERRO: {}", err_msg));
+                            continue;
+                        }
+                    };
+                    if let Some(existing) = existing {
+                        log_info(&format!(
+                            "Arquivo {} já existe como {}; upload duplicado ignorado",
+                            filename, existing.path
+                        ));
+                        // Tamanho 0: os bytes já contam na atividade do arquivo original
+                        history::record(
+                            "upload",
+                            &existing.path,
+                            0,
+                            Some(&sanitized_filename),
+                            json!({ "deduplicated": true, "duplicate_of": existing.path }),
+                        );
+                        duplicates.push(json!({
+                            "file": filename,
+                            "existing": existing.path
+                        }));
+                        continue;
                     }
 
+                    // Aplicar modelo de nome configurado
+                    let now = Local::now();
+                    let stem = filenames::render_stem(
+                        &naming.upload_template,
+                        &[
+                            ("date", now.format("%Y-%m-%d").to_string()),
+                            ("time", now.format("%H-%M").to_string()),
+                            ("name", original_stem.to_string()),
+                        ],
+                        extension,
+                    );
+                    let target_name = if extension.is_empty() {
+                        stem
+                    } else {
+                        format!("{}.{}", stem, extension)
+                    };
+
+                    // Regras de destino por tipo, data etc.
+                    let target_dir = match routing::route(
+                        &routing::Incoming {
+                            source: routing::FileSource::Upload,
+                            file_name: &target_name,
+                            sender: None,
+                            sender_id: None,
+                            received_at: now,
+                        },
+                        &state.upload_dir,
+                    )
+                    .and_then(|dir| {
+                        organize::arrival_dir(&state.upload_dir, dir, &target_name, "upload")
+                    }) {
+                        Ok(dir) => dir,
+                        Err(e) => {
+                            errors.push(e.clone());
+                            log_error(&format!("ERRO: {}", e));
+                            continue;
+                        }
+                    };
+
+                    // Aplicar política de conflito (sobrescrever, sufixo " (N)" ou ignorar)
+                    let file_path = match filenames::resolve_collision(
+                        &target_dir.join(&target_name),
+                        naming.collision_policy,
+                    ) {
+                        Resolution::Write(path) => path,
+                        Resolution::Skip(existing) => {
+                            log_info(&format!(
+                                "Arquivo {} ignorado: {} já existe",
+                                filename,
+                                existing.display()
+                            ));
+                            skipped.push(filename);
+                            continue;
+                        }
+                    };
+
                     match fs::write(&file_path, &data) {
                         Ok(_) => {
                             uploaded_count += 1;
@@ -517,17 +544,19 @@ async fn upload_files(
                             log_info(&format!(
                                 "Arquivo enviado com sucesso: {} -> {} ({})",
                                 filename,
-                                sanitized_filename,
+                                file_path.display(),
                                 format_size(data.len())
                             ));
 
                             // Registrar atividade no histórico
-                            record_activity(
+                            history::record(
                                 "upload",
                                 &file_path.to_string_lossy(),
                                 file_size,
                                 Some(&sanitized_filename),
+                                serde_json::Value::Null,
                             );
+                            content_index::record_hash(&content_hash, &file_path, file_size);
                         }
                         Err(e) => {
                             let err_msg = format!("Erro ao salvar arquivo {}: {}", filename, e);
@@ -554,6 +583,19 @@ async fn upload_files(
     }
 
     // Sempre retornar uma resposta válida, mesmo se houver erros
+    if uploaded_count == 0 && errors.is_empty() && !(skipped.is_empty() && duplicates.is_empty()) {
+        return Ok(Json(json!({
+            "message": format!(
+                "{} arquivo(s) já existiam e foram ignorados",
+                skipped.len() + duplicates.len()
+            ),
+            "count": 0,
+            "skipped": skipped,
+            "duplicates": duplicates,
+            "errors": serde_json::Value::Null
+        })));
+    }
+
     if uploaded_count == 0 && errors.is_empty() {
         return Err((
             StatusCode::BAD_REQUEST,
@@ -578,6 +620,8 @@ async fn upload_files(
     Ok(Json(json!({
         "message": format!("{} arquivo(s) enviado(s) com sucesso!", uploaded_count),
         "count": uploaded_count,
+        "skipped": skipped,
+        "duplicates": duplicates,
         "errors": if errors.is_empty() { serde_json::Value::Null } else { json!(errors) }
     })))
 }
@@ -611,7 +655,22 @@ async fn upload_links(
         .as_secs();
 
     let filename = format!("links_{}.txt", timestamp);
-    let file_path = state.upload_dir.join(&filename);
+    let target_dir = routing::route(
+        &routing::Incoming {
+            source: routing::FileSource::Upload,
+            file_name: &filename,
+            sender: None,
+            sender_id: None,
+            received_at: Local::now(),
+        },
+        &state.upload_dir,
+    )
+    .and_then(|dir| organize::arrival_dir(&state.upload_dir, dir, &filename, "upload"))
+    .unwrap_or_else(|e| {
+        log_error(&format!("ERRO: {}", e));
+        state.upload_dir.clone()
+    });
+    let file_path = target_dir.join(&filename);
 
     match fs::write(&file_path, &content) {
         Ok(_) => {
@@ -623,11 +682,12 @@ async fn upload_links(
             ));
 
             // Registrar atividade no histórico
-            record_activity(
+            history::record(
                 "upload",
                 &file_path.to_string_lossy(),
                 content.len() as u64,
                 Some("links"),
+                serde_json::Value::Null,
             );
 
             Ok(Json(json!({
@@ -40,6 +40,29 @@ function ensureDirectoryExists(dirPath) {
   }
 }
 
+// Eventos lidos pelo aplicativo (stdout), uma linha JSON por evento
+const EVENT_PREFIX = "[UPLOADIASD_EVENT]";
+
+function emitEvent(event, data) {
+  console.log(`${EVENT_PREFIX} ${JSON.stringify({ event, ...data })}`);
+}
+
+async function getSenderInfo(msg, chat) {
+  const senderId = msg.from || "";
+  const chatName = (chat && chat.name) || "";
+  try {
+    const contact = await msg.getContact();
+    return { sender: contact.pushname || contact.name || "", senderId, chatName };
+  } catch (error) {
+    return { sender: "", senderId, chatName };
+  }
+}
+
+// Texto da mensagem sem o comando e o nome do arquivo
+function getCaption(body) {
+  return body.split(" ").slice(2).join(" ").trim();
+}
+
 function saveStatus(status) {
   const payload = {
     ...status,
@@ -219,6 +242,13 @@ client.on("message_create", async (msg) => {
       fs.writeFileSync(filePath, media.data, "base64");
       await msg.reply(`Midia salva como ${fileName} com sucesso.`);
       console.log(`Media saved from message_create: ${fileName}`);
+      emitEvent("file_saved", {
+        path: filePath,
+        kind: "media",
+        mimeType: media.mimetype || "application/octet-stream",
+        caption: getCaption(msg.body),
+        ...(await getSenderInfo(msg, chat)),
+      });
     } catch (error) {
       const serialized = serializeError(error);
       console.error("Failed to download media", serialized);
@@ -259,6 +289,13 @@ client.on("message_create", async (msg) => {
       fs.writeFileSync(filePath, fileContent, "utf8");
       await msg.reply(`Links salvos no arquivo ${fileName} com sucesso.`);
       console.log(`Links saved from message_create: ${fileName}`);
+      emitEvent("file_saved", {
+        path: filePath,
+        kind: "links",
+        mimeType: "text/plain",
+        caption: "",
+        ...(await getSenderInfo(msg, chat)),
+      });
     } catch (error) {
       const serialized = serializeError(error);
       console.error("Failed to save links", serialized);
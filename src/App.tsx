@@ -6,6 +6,7 @@ import { useSettings } from "./lib/useSettings";
 import Tutorial from "./components/Tutorial/Tutorial";
 import { hasSeenWelcome } from "./lib/app-store";
 import UpdateChecker from "./components/UpdateChecker";
+import FolderStatusChecker from "./components/FolderStatusChecker";
 
 export default function App() {
   const location = useLocation();
@@ -48,6 +49,7 @@ export default function App() {
   return (
     <>
       <UpdateChecker />
+      <FolderStatusChecker />
       <div className="flex">
         <Aside />
 
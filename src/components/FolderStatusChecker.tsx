@@ -0,0 +1,78 @@
+import { useEffect } from "react";
+import { useNavigate } from "react-router-dom";
+import { toast } from "sonner";
+import {
+  FolderStatus,
+  FOLDER_LABELS,
+  getFolderStatus,
+  switchToDefaultFolder,
+} from "../lib/folder-status";
+
+// Na abertura do app, avisar sobre pastas configuradas indisponíveis (ex.: pendrive desconectado)
+export default function FolderStatusChecker() {
+  const navigate = useNavigate();
+
+  useEffect(() => {
+    if (typeof window === "undefined" || !("__TAURI__" in window)) {
+      return;
+    }
+
+    getFolderStatus()
+      .then((statuses) => {
+        statuses
+          .filter((status) => !status.available)
+          .forEach((status) => showUnavailable(status));
+      })
+      .catch((error) => {
+        console.error("Erro ao verificar pastas:", error);
+      });
+  }, []);
+
+  const showUnavailable = (status: FolderStatus) => {
+    const label = FOLDER_LABELS[status.kind];
+
+    toast.warning(
+      <div className="flex flex-col gap-2">
+        <div className="font-semibold">Pasta de {label} indisponível</div>
+        <div className="text-xs text-gray-600 break-all">{status.path}</div>
+        {status.error && (
+          <div className="text-xs text-gray-600">{status.error}</div>
+        )}
+        <div className="flex gap-2 mt-2">
+          <button
+            onClick={async () => {
+              try {
+                const path = await switchToDefaultFolder(status.kind);
+                toast.dismiss(`folder-status-${status.kind}`);
+                toast.success(`Usando a pasta padrão de ${label}: ${path}`);
+              } catch (error) {
+                console.error("Erro ao usar pasta padrão:", error);
+                toast.error(String(error));
+              }
+            }}
+            className="px-3 py-1 bg-blue-600 text-white rounded text-xs hover:bg-blue-700 transition-colors"
+          >
+            Usar pasta padrão
+          </button>
+          <button
+            onClick={() => {
+              toast.dismiss(`folder-status-${status.kind}`);
+              navigate("/configuracoes");
+            }}
+            className="px-3 py-1 bg-gray-300 text-gray-700 rounded text-xs hover:bg-gray-400 transition-colors"
+          >
+            Configurações
+          </button>
+        </div>
+      </div>,
+      {
+        duration: Infinity,
+        id: `folder-status-${status.kind}`,
+        position: "bottom-right",
+      }
+    );
+  };
+
+  // Componente não renderiza nada; os avisos aparecem como toast
+  return null;
+}
@@ -0,0 +1,40 @@
+import { invoke } from "@tauri-apps/api/core";
+import { getSettingsStore } from "./settings-store";
+
+export type FolderKind = "uploads" | "videos";
+
+export interface FolderStatus {
+  kind: FolderKind;
+  path: string;
+  usingDefault: boolean;
+  available: boolean;
+  freeBytes: number | null;
+  error: string | null;
+  fallbackPath: string;
+}
+
+export const FOLDER_LABELS: Record<FolderKind, string> = {
+  uploads: "uploads",
+  videos: "vídeos",
+};
+
+export async function getFolderStatus(): Promise<FolderStatus[]> {
+  const response = await invoke<string>("get_folder_status");
+  return JSON.parse(response) as FolderStatus[];
+}
+
+// Voltar para a pasta padrão no backend e limpar o caminho salvo no store da interface,
+// senão o próximo "Salvar Configurações" gravaria a pasta indisponível de novo
+export async function switchToDefaultFolder(kind: FolderKind): Promise<string> {
+  const defaultPath = await invoke<string>("use_default_folder", { kind });
+
+  const store = await getSettingsStore();
+  const saved = await store.get<Record<string, unknown>>("settings");
+  if (saved) {
+    const key = kind === "uploads" ? "uploadsPath" : "videosPath";
+    await store.set("settings", { ...saved, [key]: "" });
+    await store.save();
+  }
+
+  return defaultPath;
+}
@@ -3,6 +3,12 @@ import { useSettings } from "../../lib/useSettings";
 import { FiSave, FiUpload, FiImage, FiDroplet, FiHome, FiSettings, FiFolder, FiVideo, FiFile } from "react-icons/fi";
 import { toast } from "sonner";
 import { open as openDialog } from "@tauri-apps/plugin-dialog";
+import {
+  FolderKind,
+  FolderStatus,
+  getFolderStatus,
+  switchToDefaultFolder,
+} from "../../lib/folder-status";
 
 const PRESET_COLORS = [
   { name: "Azul Escuro", value: "#003366" },
@@ -26,8 +32,61 @@ export default function SettingsPage() {
   });
   const [logoPreview, setLogoPreview] = useState<string>("");
   const [forceShow, setForceShow] = useState(false);
+  const [folderStatus, setFolderStatus] = useState<FolderStatus[]>([]);
   const fileInputRef = useRef<HTMLInputElement>(null);
 
+  const loadFolderStatus = () => {
+    if (typeof window === "undefined" || !("__TAURI__" in window)) {
+      return;
+    }
+    getFolderStatus()
+      .then(setFolderStatus)
+      .catch((error) => console.error("Erro ao verificar pastas:", error));
+  };
+
+  useEffect(() => {
+    loadFolderStatus();
+  }, []);
+
+  const handleUseDefaultFolder = async (kind: FolderKind) => {
+    try {
+      const path = await switchToDefaultFolder(kind);
+      setFormData((prev) => ({
+        ...prev,
+        [kind === "uploads" ? "uploadsPath" : "videosPath"]: "",
+      }));
+      toast.success(`Usando a pasta padrão: ${path}`);
+      loadFolderStatus();
+    } catch (error) {
+      console.error("Erro ao usar pasta padrão:", error);
+      toast.error(String(error));
+    }
+  };
+
+  // Aviso de pasta configurada indisponível, com opção de voltar para a padrão
+  const renderFolderWarning = (kind: FolderKind) => {
+    const status = folderStatus.find((s) => s.kind === kind);
+    if (!status || status.available) {
+      return null;
+    }
+
+    return (
+      <div className="mt-2 p-3 bg-red-50 border border-red-200 rounded-lg text-sm text-red-700 flex items-center justify-between gap-3">
+        <span>
+          Pasta indisponível{status.error ? `: ${status.error}` : ""}
+        </span>
+        {!status.usingDefault && (
+          <button
+            onClick={() => handleUseDefaultFolder(kind)}
+            className="px-3 py-1 bg-red-600 text-white rounded text-xs hover:bg-red-700 transition-colors whitespace-nowrap cursor-pointer"
+          >
+            Usar pasta padrão
+          </button>
+        )}
+      </div>
+    );
+  };
+
 
   useEffect(() => {
     if (!loading && settings) {
@@ -110,6 +169,9 @@ export default function SettingsPage() {
         await invoke("set_uploads_path", { path: formData.uploadsPath });
       } catch (error) {
         console.error("Erro ao salvar caminho de uploads:", error);
+        // Pasta inválida, sem permissão de escrita ou indisponível
+        toast.error(String(error));
+        return;
       }
     }
 
@@ -119,6 +181,9 @@ export default function SettingsPage() {
         await invoke("set_videos_path", { path: formData.videosPath });
       } catch (error) {
         console.error("Erro ao salvar caminho de vídeos:", error);
+        // Pasta inválida, sem permissão de escrita ou indisponível
+        toast.error(String(error));
+        return;
       }
     }
 
@@ -362,6 +427,7 @@ export default function SettingsPage() {
               </button>
             )}
           </div>
+          {renderFolderWarning("uploads")}
           <p className="text-xs text-gray-500 mt-2">
             Pasta onde serão salvos os arquivos enviados via upload e bot WhatsApp
           </p>
@@ -439,6 +505,7 @@ export default function SettingsPage() {
               </button>
             )}
           </div>
+          {renderFolderWarning("videos")}
           <p className="text-xs text-gray-500 mt-2">
             Pasta onde serão salvos os vídeos baixados do YouTube
           </p>
@@ -6,6 +6,20 @@ import { useSettings } from "../../lib/useSettings";
 
 type DownloadType = "video" | "audio" | null;
 type Quality = "best" | "2160p" | "1440p" | "1080p" | "720p" | "480p" | "360p" | "240p";
+type AudioFormat = "mp3" | "m4a" | "opus" | "wav" | "flac";
+type VideoFormat = "mp4" | "mkv" | "webm";
+
+// Apenas formatos com perda aceitam escolha de bitrate
+const LOSSY_AUDIO_FORMATS: AudioFormat[] = ["mp3", "m4a", "opus"];
+
+// "duplicate": vídeo já baixado antes; "skipped": já existia arquivo com o mesmo nome
+type DownloadStatus = "downloaded" | "duplicate" | "skipped";
+
+interface DownloadResult {
+  path: string;
+  name: string;
+  status: DownloadStatus;
+}
 
 interface VideoInfo {
   title: string;
@@ -20,9 +34,13 @@ export default function YoutubePage() {
   const [url, setUrl] = useState("");
   const [downloadType, setDownloadType] = useState<DownloadType>(null);
   const [quality, setQuality] = useState<Quality>("best");
+  const [audioFormat, setAudioFormat] = useState<AudioFormat>("mp3");
+  const [videoFormat, setVideoFormat] = useState<VideoFormat>("mp4");
+  // Vazio = melhor qualidade (VBR)
+  const [audioBitrate, setAudioBitrate] = useState<string>("");
   const [isDownloading, setIsDownloading] = useState(false);
   const [progress, setProgress] = useState<string>("");
-  const [downloadedFile, setDownloadedFile] = useState<{ path: string; name: string } | null>(null);
+  const [downloadedFile, setDownloadedFile] = useState<DownloadResult | null>(null);
   const [videoInfo, setVideoInfo] = useState<VideoInfo | null>(null);
 
   const isValidYoutubeUrl = (url: string): boolean => {
@@ -86,7 +104,7 @@ export default function YoutubePage() {
     return () => clearTimeout(timeoutId);
   }, [url]);
 
-  const handleDownload = async () => {
+  const handleDownload = async (allowDuplicate = false) => {
     if (!url.trim()) {
       toast.error("Por favor, insira a URL do vídeo do YouTube");
       return;
@@ -110,14 +128,18 @@ export default function YoutubePage() {
       setProgress("Preparando download...");
       const result = await invoke<string>("download_youtube", {
         url: url.trim(),
-        format: downloadType,
+        format: downloadType === "audio" ? audioFormat : videoFormat,
         quality: downloadType === "video" ? quality : null,
+        audioBitrate:
+          downloadType === "audio" && LOSSY_AUDIO_FORMATS.includes(audioFormat) && audioBitrate
+            ? audioBitrate
+            : null,
+        allowDuplicate,
       });
 
-      // Separar caminho completo e nome do arquivo
-      const [filePath, fileName] = result.split("|");
-      
-      setDownloadedFile({ path: filePath, name: fileName });
+      const downloaded: DownloadResult = JSON.parse(result);
+      const fileName = downloaded.name;
+      setDownloadedFile(downloaded);
       
       // Se não tiver videoInfo, tentar buscar novamente após o download
       if (!videoInfo && url.trim()) {
@@ -136,17 +158,74 @@ export default function YoutubePage() {
         }
       }
       
-      toast.success("Download concluído com sucesso!");
-      setProgress(`Download concluído: ${fileName}`);
+      if (downloaded.status === "duplicate") {
+        toast.info("Este vídeo já foi baixado antes");
+        setProgress(`Já existe: ${fileName}`);
+      } else if (downloaded.status === "skipped") {
+        toast.info("Já existe um arquivo com este nome; download ignorado");
+        setProgress(`Download ignorado: ${fileName}`);
+      } else {
+        toast.success("Download concluído com sucesso!");
+        setProgress(`Download concluído: ${fileName}`);
+      }
     } catch (error: any) {
       console.error("Erro ao baixar:", error);
-      const errorMessage = error?.message || error?.toString() || "Erro desconhecido";
-      
-      // Verificar se é erro de Tauri não disponível
-      if (errorMessage.includes("invoke") || errorMessage.includes("Tauri") || errorMessage.includes("not found")) {
+      let errorMessage = error?.message || error?.toString() || "Erro desconhecido";
+
+      // Erros do yt-dlp chegam como JSON: {"code", "message", "detail"}
+      let parsed: { code?: string; message?: string; detail?: string } | null = null;
+      try {
+        parsed = JSON.parse(errorMessage);
+      } catch {
+        // Mensagem em texto simples
+      }
+
+      if (parsed?.code && parsed?.message) {
+        let status: string;
+        switch (parsed.code) {
+          case "ffmpeg_missing":
+            status = "Erro: FFmpeg não encontrado";
+            break;
+          case "network":
+            status = "Erro: falha de conexão";
+            break;
+          case "outdated_extractor":
+            status = "Erro: yt-dlp possivelmente desatualizado";
+            break;
+          case "disk_full":
+            status = "Erro: sem espaço em disco";
+            break;
+          case "format_unavailable":
+            status = "Erro: formato indisponível";
+            break;
+          case "video_unavailable":
+            status = "Erro: vídeo indisponível";
+            break;
+          case "geo_blocked":
+            status = "Erro: vídeo bloqueado na região";
+            break;
+          case "live_not_started":
+            status = "Erro: transmissão ainda não começou";
+            break;
+          case "http_403":
+          case "http_416":
+            status = "Erro: o YouTube recusou o download";
+            break;
+          case "age_restricted":
+          case "members_only":
+          case "sign_in_required":
+            status = "Erro: o vídeo exige login (cookies)";
+            break;
+          default:
+            status = `Erro: ${parsed.message}`;
+        }
+        toast.error(parsed.message);
+        setProgress(status);
+      } else if (errorMessage.includes("invoke") || errorMessage.includes("Tauri") || errorMessage.includes("not found")) {
+        // Verificar se é erro de Tauri não disponível
         toast.error("Funcionalidade disponível apenas na versão desktop. Execute o aplicativo Tauri.");
         setProgress("Execute o aplicativo desktop para usar esta funcionalidade");
-      } else if (errorMessage.includes("yt-dlp não encontrado") || errorMessage.includes("não encontrado")) {
+      } else if (errorMessage.includes("Erro ao baixar yt-dlp")) {
         toast.error("Erro ao baixar yt-dlp. Verifique sua conexão com a internet.");
         setProgress("Erro: yt-dlp não encontrado");
       } else {
@@ -286,7 +365,7 @@ export default function YoutubePage() {
               <div className="text-center">
                 <h3 className="font-semibold text-gray-900">Vídeo</h3>
                 <p className="text-xs text-gray-500 mt-1">
-                  Baixa o vídeo completo com áudio
+                  Baixa o vídeo completo com áudio (MP4, MKV ou WebM)
                 </p>
               </div>
             </button>
@@ -312,13 +391,78 @@ export default function YoutubePage() {
               <div className="text-center">
                 <h3 className="font-semibold text-gray-900">Áudio</h3>
                 <p className="text-xs text-gray-500 mt-1">
-                  Baixa apenas o áudio (MP3)
+                  Baixa apenas o áudio (MP3, M4A, Opus, WAV ou FLAC)
                 </p>
               </div>
             </button>
           </div>
         </div>
 
+        {/* Formato e bitrate (apenas para áudio) */}
+        {downloadType === "audio" && (
+          <div className="mb-6 grid grid-cols-1 md:grid-cols-2 gap-4">
+            <div>
+              <label className="block text-sm font-medium text-gray-700 mb-3">
+                Formato do Áudio
+              </label>
+              <select
+                value={audioFormat}
+                onChange={(e) => setAudioFormat(e.target.value as AudioFormat)}
+                disabled={isDownloading}
+                className="w-full px-4 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-primary focus:border-transparent bg-white"
+              >
+                <option value="mp3">MP3</option>
+                <option value="m4a">M4A (podcast)</option>
+                <option value="opus">Opus</option>
+                <option value="wav">WAV (mesa de som)</option>
+                <option value="flac">FLAC (sem perdas)</option>
+              </select>
+            </div>
+            <div>
+              <label className="block text-sm font-medium text-gray-700 mb-3">
+                Bitrate
+              </label>
+              <select
+                value={audioBitrate}
+                onChange={(e) => setAudioBitrate(e.target.value)}
+                disabled={isDownloading || !LOSSY_AUDIO_FORMATS.includes(audioFormat)}
+                className="w-full px-4 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-primary focus:border-transparent bg-white disabled:bg-gray-100"
+              >
+                <option value="">Melhor disponível</option>
+                <option value="320k">320 kbps</option>
+                <option value="256k">256 kbps</option>
+                <option value="192k">192 kbps</option>
+                <option value="128k">128 kbps</option>
+                <option value="96k">96 kbps</option>
+              </select>
+              {!LOSSY_AUDIO_FORMATS.includes(audioFormat) && (
+                <p className="text-xs text-gray-500 mt-2">
+                  WAV e FLAC são sem perdas; o bitrate não se aplica
+                </p>
+              )}
+            </div>
+          </div>
+        )}
+
+        {/* Formato do vídeo */}
+        {downloadType === "video" && (
+          <div className="mb-6">
+            <label className="block text-sm font-medium text-gray-700 mb-3">
+              Formato do Vídeo
+            </label>
+            <select
+              value={videoFormat}
+              onChange={(e) => setVideoFormat(e.target.value as VideoFormat)}
+              disabled={isDownloading}
+              className="w-full px-4 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-primary focus:border-transparent bg-white"
+            >
+              <option value="mp4">MP4</option>
+              <option value="mkv">MKV</option>
+              <option value="webm">WebM</option>
+            </select>
+          </div>
+        )}
+
         {/* Qualidade (apenas para vídeo) */}
         {downloadType === "video" && (
           <div className="mb-6">
@@ -368,8 +512,44 @@ export default function YoutubePage() {
           </div>
         )}
 
+        {/* Vídeo já baixado antes: abrir o existente ou baixar mesmo assim */}
+        {downloadedFile?.status === "duplicate" && !isDownloading && (
+          <div className="mb-6 p-4 bg-yellow-50 border-2 border-yellow-300 rounded-lg shadow-sm">
+            <p className="text-sm font-bold text-yellow-900 mb-2">Você já tem este vídeo</p>
+            <div className="bg-white/50 rounded p-2 mb-3">
+              <p className="text-xs font-medium text-gray-600 mb-1">Arquivo existente:</p>
+              <p className="text-yellow-900 font-mono text-xs break-all">{downloadedFile.path}</p>
+            </div>
+            <div className="flex gap-3">
+              <button
+                onClick={async () => {
+                  try {
+                    const { openPath } = await import("@tauri-apps/plugin-opener");
+                    await openPath(downloadedFile.path);
+                  } catch (error: any) {
+                    console.error("Erro ao abrir arquivo:", error);
+                    toast.error(`Erro ao abrir arquivo: ${error?.message || error}`);
+                  }
+                }}
+                className="px-4 py-2 text-white rounded-lg transition-opacity flex items-center gap-2 text-sm font-medium shadow-sm hover:opacity-90 cursor-pointer"
+                style={{ backgroundColor: settingsLoading ? "#9ca3af" : settings.primaryColor }}
+              >
+                <FiFolder />
+                Abrir arquivo
+              </button>
+              <button
+                onClick={() => handleDownload(true)}
+                className="px-4 py-2 bg-gray-200 hover:bg-gray-300 text-gray-700 rounded-lg transition-colors flex items-center gap-2 text-sm font-medium cursor-pointer"
+              >
+                <FiDownload />
+                Baixar mesmo assim
+              </button>
+            </div>
+          </div>
+        )}
+
         {/* Arquivo baixado */}
-        {downloadedFile && !isDownloading && (
+        {downloadedFile && downloadedFile.status !== "duplicate" && !isDownloading && (
           <div className="mb-6 p-4 bg-green-50 border-2 border-green-300 rounded-lg shadow-sm">
             <div className="flex items-start gap-4">
               {/* Thumbnail do vídeo */}
@@ -388,7 +568,9 @@ export default function YoutubePage() {
                   <div className="flex items-center gap-2 mb-2">
                     <div className="h-2 w-2 bg-green-500 rounded-full animate-pulse"></div>
                     <p className="text-sm font-bold text-green-900">
-                      Download concluído com sucesso!
+                      {downloadedFile.status === "skipped"
+                        ? "Arquivo já existia; download ignorado"
+                        : "Download concluído com sucesso!"}
                     </p>
                   </div>
                   {videoInfo && (
@@ -465,7 +647,7 @@ export default function YoutubePage() {
         {/* Botões */}
         <div className="flex gap-3">
           <button
-            onClick={handleDownload}
+            onClick={() => handleDownload()}
             disabled={isDownloading || !url.trim() || !downloadType}
             className="flex-1 px-6 py-3 disabled:bg-gray-400 disabled:cursor-not-allowed text-white rounded-lg transition-opacity flex items-center justify-center gap-2 font-medium hover:opacity-90 cursor-pointer"
             style={{ 